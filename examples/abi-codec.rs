@@ -0,0 +1,931 @@
+//! Encodes and decodes instruction data of any program from its ABI, as written
+//! to the `*_ABI.json` files by `ProgramInstruction::abi()`
+
+use program_instruction::{FieldAbi, InstructionAbi, ProgramAbi, TypeAbi, TypeKind};
+use serde_json::{Map, Value};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use std::{convert::TryInto, fmt, str::FromStr};
+
+/// Errors raised while encoding or decoding an instruction. `field` is the
+/// path of the value in the JSON arguments, e.g. `data.creators[0].share`.
+#[derive(Debug)]
+pub enum CodecError {
+    Json(serde_json::Error),
+    UnknownInstruction(String),
+    UnknownCode(u8),
+    UnknownType(String),
+    MissingArgument(String),
+    InvalidArgument {
+        field: String,
+        expected: String,
+    },
+    MissingAccount(String),
+    InvalidAccount {
+        name: String,
+        expected: String,
+    },
+    EmptyInput,
+    Truncated {
+        field: String,
+        offset: usize,
+        input_len: usize,
+    },
+    InvalidData {
+        field: String,
+        offset: usize,
+    },
+    TrailingBytes {
+        expected_len: usize,
+        trailing_len: usize,
+    },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Json(error) => write!(f, "invalid ABI: {}", error),
+            Self::UnknownInstruction(name) => write!(f, "unknown instruction {}", name),
+            Self::UnknownCode(code) => write!(f, "unknown instruction code {}", code),
+            Self::UnknownType(ty) => write!(f, "unknown type {}", ty),
+            Self::MissingArgument(field) => write!(f, "missing argument {}", field),
+            Self::InvalidArgument { field, expected } => {
+                write!(f, "invalid argument {}, expected {}", field, expected)
+            }
+            Self::MissingAccount(name) => write!(f, "missing account {}", name),
+            Self::InvalidAccount { name, expected } => {
+                write!(f, "invalid account {}, expected {}", name, expected)
+            }
+            Self::EmptyInput => write!(f, "empty instruction data"),
+            Self::Truncated {
+                field,
+                offset,
+                input_len,
+            } => write!(
+                f,
+                "instruction data truncated at {} (offset {}, {} bytes)",
+                field, offset, input_len
+            ),
+            Self::InvalidData { field, offset } => {
+                write!(f, "invalid value for {} at offset {}", field, offset)
+            }
+            Self::TrailingBytes {
+                expected_len,
+                trailing_len,
+            } => write!(
+                f,
+                "{} trailing bytes after the {} bytes of the instruction",
+                trailing_len, expected_len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<serde_json::Error> for CodecError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+/// Length of an array type
+#[derive(Clone, Debug, PartialEq)]
+enum Length {
+    Fixed(usize),
+    /// Held by a previous field
+    Field(String),
+    /// Up to the end of the instruction data
+    Rest,
+}
+
+/// Parsed form of the type strings of [FieldAbi](../program_instruction/struct.FieldAbi.html)
+#[derive(Clone, Debug, PartialEq)]
+enum Type {
+    Unsigned(usize),
+    Signed(usize),
+    Bool,
+    Pubkey,
+    String,
+    Array(Box<Type>, Length),
+    Option(Box<Type>),
+    Vec(Box<Type>),
+    Named(String),
+}
+
+impl Type {
+    fn parse(ty: &str) -> Result<Self, CodecError> {
+        let ty = ty.trim();
+        if let Some(inner) = strip_generic(ty, "Option") {
+            return Ok(Self::Option(Box::new(Self::parse(inner)?)));
+        }
+        if let Some(inner) = strip_generic(ty, "Vec") {
+            return Ok(Self::Vec(Box::new(Self::parse(inner)?)));
+        }
+        if let Some(index) = ty.rfind(';') {
+            let element = Self::parse(&ty[..index])?;
+            let length = match ty[index + 1..].trim() {
+                ".." => Length::Rest,
+                length => match length.parse() {
+                    Ok(length) => Length::Fixed(length),
+                    Err(_) => Length::Field(length.to_string()),
+                },
+            };
+            return Ok(Self::Array(Box::new(element), length));
+        }
+        Ok(match ty {
+            "u8" => Self::Unsigned(1),
+            "u16" => Self::Unsigned(2),
+            "u32" => Self::Unsigned(4),
+            "u64" => Self::Unsigned(8),
+            "u128" => Self::Unsigned(16),
+            "i8" => Self::Signed(1),
+            "i16" => Self::Signed(2),
+            "i32" => Self::Signed(4),
+            "i64" => Self::Signed(8),
+            "i128" => Self::Signed(16),
+            "bool" => Self::Bool,
+            "Pubkey" => Self::Pubkey,
+            "String" => Self::String,
+            "" => return Err(CodecError::UnknownType(ty.to_string())),
+            name => Self::Named(name.to_string()),
+        })
+    }
+}
+
+fn strip_generic<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+    ty.strip_prefix(name)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// Decoded instruction data
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedInstruction<'a> {
+    pub instruction: &'a InstructionAbi,
+    /// Inputs by name, in wire order
+    pub fields: Map<String, Value>,
+    /// Number of bytes after the last input, ignored like the programs do
+    pub trailing_len: usize,
+}
+
+/// Instruction data codec driven by a [ProgramAbi](../program_instruction/struct.ProgramAbi.html).
+///
+/// Arguments are a JSON object of the inputs: integers as numbers or decimal
+/// strings, `Pubkey` as base58 strings, `Option` as `null` or the value, arrays
+/// and `Vec` as arrays, structs as objects and enums as the variant name.
+/// Length fields of `T;field` arrays can be left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AbiCodec {
+    abi: ProgramAbi,
+}
+
+impl AbiCodec {
+    pub fn new(abi: ProgramAbi) -> Self {
+        Self { abi }
+    }
+
+    /// Reads the content of a `*_ABI.json` file
+    pub fn from_json(json: &str) -> Result<Self, CodecError> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    pub fn abi(&self) -> &ProgramAbi {
+        &self.abi
+    }
+
+    /// Returns the first instruction of this name
+    pub fn instruction(&self, name: &str) -> Result<&InstructionAbi, CodecError> {
+        self.abi
+            .instructions
+            .iter()
+            .find(|instruction| instruction.name == name)
+            .ok_or_else(|| CodecError::UnknownInstruction(name.to_string()))
+    }
+
+    pub fn instruction_by_code(&self, code: u8) -> Result<&InstructionAbi, CodecError> {
+        self.abi
+            .instructions
+            .iter()
+            .find(|instruction| instruction.code == code)
+            .ok_or(CodecError::UnknownCode(code))
+    }
+
+    /// Encodes the instruction data, `args` is an object of the inputs or `null` when there are none
+    pub fn encode_data(&self, name: &str, args: &Value) -> Result<Vec<u8>, CodecError> {
+        self.encode_instruction_data(self.instruction(name)?, args)
+    }
+
+    pub fn encode_instruction_data(
+        &self,
+        instruction: &InstructionAbi,
+        args: &Value,
+    ) -> Result<Vec<u8>, CodecError> {
+        let empty = Map::new();
+        let args = match args {
+            Value::Object(args) => args,
+            Value::Null => &empty,
+            _ => {
+                return Err(CodecError::InvalidArgument {
+                    field: instruction.name.clone(),
+                    expected: "object".to_string(),
+                })
+            }
+        };
+        let mut encoder = Encoder {
+            types: &self.abi.types,
+            buf: vec![instruction.code],
+        };
+        encoder.encode_fields(&instruction.inputs, args, "")?;
+        Ok(encoder.buf)
+    }
+
+    /// Builds the account list from an object of pubkeys by account name.
+    /// Repeated accounts take an array and optional accounts can be left out.
+    pub fn encode_accounts(&self, name: &str, accounts: &Value) -> Result<Vec<AccountMeta>, CodecError> {
+        let instruction = self.instruction(name)?;
+        let mut metas = Vec::with_capacity(instruction.accounts.len());
+        for account in instruction.accounts.iter() {
+            let keys = match accounts.get(&account.name) {
+                None | Some(Value::Null) if account.is_optional || account.is_repeated => vec![],
+                None | Some(Value::Null) => return Err(CodecError::MissingAccount(account.name.clone())),
+                Some(Value::Array(keys)) if account.is_repeated => keys.iter().collect(),
+                Some(key) if !account.is_repeated => vec![key],
+                Some(_) => {
+                    return Err(CodecError::InvalidAccount {
+                        name: account.name.clone(),
+                        expected: "array".to_string(),
+                    })
+                }
+            };
+            for key in keys {
+                let pubkey = key
+                    .as_str()
+                    .and_then(|key| Pubkey::from_str(key).ok())
+                    .ok_or_else(|| CodecError::InvalidAccount {
+                        name: account.name.clone(),
+                        expected: "base58 pubkey".to_string(),
+                    })?;
+                metas.push(if account.is_readonly {
+                    AccountMeta::new_readonly(pubkey, account.is_signer)
+                } else {
+                    AccountMeta::new(pubkey, account.is_signer)
+                });
+            }
+        }
+        Ok(metas)
+    }
+
+    pub fn encode(
+        &self,
+        program_id: &Pubkey,
+        name: &str,
+        args: &Value,
+        accounts: &Value,
+    ) -> Result<Instruction, CodecError> {
+        Ok(Instruction {
+            program_id: *program_id,
+            accounts: self.encode_accounts(name, accounts)?,
+            data: self.encode_data(name, args)?,
+        })
+    }
+
+    /// Decodes instruction data into named fields. Like the programs, bytes after the last
+    /// input are ignored, their count is kept in `trailing_len`.
+    pub fn decode_data<'a>(&'a self, data: &[u8]) -> Result<DecodedInstruction<'a>, CodecError> {
+        let (&code, _) = data.split_first().ok_or(CodecError::EmptyInput)?;
+        let instruction = self.instruction_by_code(code)?;
+        let mut decoder = Decoder {
+            types: &self.abi.types,
+            input: data,
+            offset: 1,
+        };
+        let fields = decoder.decode_fields(&instruction.inputs, "")?;
+        Ok(DecodedInstruction {
+            instruction,
+            fields,
+            trailing_len: data.len() - decoder.offset,
+        })
+    }
+
+    /// Decodes instruction data like [decode_data](#method.decode_data), rejecting trailing bytes
+    pub fn decode_data_strict<'a>(&'a self, data: &[u8]) -> Result<DecodedInstruction<'a>, CodecError> {
+        let decoded = self.decode_data(data)?;
+        if decoded.trailing_len != 0 {
+            return Err(CodecError::TrailingBytes {
+                expected_len: data.len() - decoded.trailing_len,
+                trailing_len: decoded.trailing_len,
+            });
+        }
+        Ok(decoded)
+    }
+}
+
+/// Names the accounts passed to an instruction. Repeated accounts evenly share
+/// the accounts left by the others and are suffixed with their index, optional
+/// accounts are named in order while accounts remain.
+pub fn account_labels(instruction: &InstructionAbi, count: usize) -> Vec<String> {
+    let accounts = &instruction.accounts;
+    let required = accounts
+        .iter()
+        .filter(|account| !account.is_optional && !account.is_repeated)
+        .count();
+    let repeated = accounts.iter().filter(|account| account.is_repeated).count();
+    let extra = count.saturating_sub(required);
+    let (per_repeated, mut optional) = match repeated {
+        0 => (0, extra),
+        repeated => (extra / repeated, extra % repeated),
+    };
+    let mut labels = Vec::with_capacity(count);
+    for account in accounts.iter() {
+        if account.is_repeated {
+            labels.extend((0..per_repeated).map(|index| format!("{}[{}]", account.name, index)));
+        } else if !account.is_optional {
+            labels.push(account.name.clone());
+        } else if optional > 0 {
+            labels.push(account.name.clone());
+            optional -= 1;
+        }
+    }
+    labels.truncate(count);
+    while labels.len() < count {
+        labels.push(format!("unknown[{}]", labels.len()));
+    }
+    labels
+}
+
+fn field_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+fn find_type<'a>(types: &'a [TypeAbi], name: &str) -> Result<&'a TypeKind, CodecError> {
+    types
+        .iter()
+        .find(|ty| ty.name == name)
+        .map(|ty| &ty.kind)
+        .ok_or_else(|| CodecError::UnknownType(name.to_string()))
+}
+
+/// Value of a previous integer field, for conditions and array lengths
+fn field_value(fields: &Map<String, Value>, name: &str, path: &str) -> Result<u64, CodecError> {
+    fields
+        .get(name)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| CodecError::MissingArgument(field_path(path, name)))
+}
+
+struct Encoder<'a> {
+    types: &'a [TypeAbi],
+    buf: Vec<u8>,
+}
+
+impl<'a> Encoder<'a> {
+    fn encode_fields(&mut self, fields: &[FieldAbi], args: &Map<String, Value>, path: &str) -> Result<(), CodecError> {
+        let types = fields
+            .iter()
+            .map(|field| Type::parse(&field.ty))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Length fields default to the length of their array
+        let mut lengths = Map::new();
+        for (field, ty) in fields.iter().zip(types.iter()) {
+            if let (Type::Array(_, Length::Field(length)), Some(Value::Array(values))) = (ty, args.get(&field.name)) {
+                lengths.insert(length.clone(), Value::from(values.len()));
+            }
+        }
+        let mut encoded = Map::new();
+        for (field, ty) in fields.iter().zip(types.iter()) {
+            let field_path = field_path(path, &field.name);
+            if let Some(condition) = &field.when {
+                if !condition.holds(field_value(&encoded, &condition.field, path)?) {
+                    continue;
+                }
+            }
+            let value = match (args.get(&field.name), lengths.get(&field.name)) {
+                (Some(value), Some(length)) if value.as_u64() != length.as_u64() => {
+                    return Err(CodecError::InvalidArgument {
+                        field: field_path,
+                        expected: format!("length {}", length),
+                    })
+                }
+                (Some(value), _) | (None, Some(value)) => value,
+                (None, None) => return Err(CodecError::MissingArgument(field_path)),
+            };
+            if let Type::Array(_, Length::Field(length)) = ty {
+                let length = field_value(&encoded, length, path)? as usize;
+                self.encode_array(ty, value, Some(length), &field_path)?;
+            } else {
+                self.encode(ty, value, &field_path)?;
+            }
+            encoded.insert(field.name.clone(), value.clone());
+        }
+        Ok(())
+    }
+
+    fn invalid(path: &str, expected: &str) -> CodecError {
+        CodecError::InvalidArgument {
+            field: path.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    fn encode(&mut self, ty: &Type, value: &Value, path: &str) -> Result<(), CodecError> {
+        match ty {
+            Type::Unsigned(size) => {
+                let parsed = match value {
+                    Value::Number(number) => number.as_u64().map(u128::from),
+                    Value::String(string) => string.parse::<u128>().ok(),
+                    _ => None,
+                };
+                let value = parsed
+                    .filter(|value| *size == 16 || *value >> (8 * size) == 0)
+                    .ok_or_else(|| Self::invalid(path, &format!("u{}", 8 * size)))?;
+                self.buf.extend_from_slice(&value.to_le_bytes()[..*size]);
+            }
+            Type::Signed(size) => {
+                let parsed = match value {
+                    Value::Number(number) => number.as_i64().map(i128::from),
+                    Value::String(string) => string.parse::<i128>().ok(),
+                    _ => None,
+                };
+                let bits = 8 * *size as u32;
+                let value = parsed
+                    .filter(|value| bits == 128 || (*value >> (bits - 1) == 0 || *value >> (bits - 1) == -1))
+                    .ok_or_else(|| Self::invalid(path, &format!("i{}", bits)))?;
+                self.buf.extend_from_slice(&value.to_le_bytes()[..*size]);
+            }
+            Type::Bool => {
+                let value = value.as_bool().ok_or_else(|| Self::invalid(path, "bool"))?;
+                self.buf.push(value as u8);
+            }
+            Type::Pubkey => {
+                let pubkey = value
+                    .as_str()
+                    .and_then(|value| Pubkey::from_str(value).ok())
+                    .ok_or_else(|| Self::invalid(path, "base58 pubkey"))?;
+                self.buf.extend_from_slice(&pubkey.to_bytes());
+            }
+            Type::String => {
+                let value = value.as_str().ok_or_else(|| Self::invalid(path, "string"))?;
+                self.buf.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                self.buf.extend_from_slice(value.as_bytes());
+            }
+            Type::Array(_, Length::Fixed(length)) => self.encode_array(ty, value, Some(*length), path)?,
+            Type::Array(_, Length::Rest) => self.encode_array(ty, value, None, path)?,
+            // Only valid at the level of the field holding the length
+            Type::Array(_, Length::Field(length)) => return Err(CodecError::MissingArgument(length.clone())),
+            Type::Option(inner) => match value {
+                Value::Null => self.buf.push(0),
+                value => {
+                    self.buf.push(1);
+                    self.encode(inner, value, path)?;
+                }
+            },
+            Type::Vec(inner) => {
+                let values = value.as_array().ok_or_else(|| Self::invalid(path, "array"))?;
+                self.buf.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                for (index, value) in values.iter().enumerate() {
+                    self.encode(inner, value, &format!("{}[{}]", path, index))?;
+                }
+            }
+            Type::Named(name) => match find_type(self.types, name)? {
+                TypeKind::Struct { fields } => {
+                    let args = value.as_object().ok_or_else(|| Self::invalid(path, name))?;
+                    self.encode_fields(fields, args, path)?;
+                }
+                TypeKind::Enum { variants } => {
+                    let index = value
+                        .as_str()
+                        .and_then(|value| variants.iter().position(|variant| variant == value))
+                        .ok_or_else(|| Self::invalid(path, &variants.join(" | ")))?;
+                    self.buf.push(index as u8);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn encode_array(&mut self, ty: &Type, value: &Value, length: Option<usize>, path: &str) -> Result<(), CodecError> {
+        let element = match ty {
+            Type::Array(element, _) => element,
+            _ => unreachable!(),
+        };
+        let values = value.as_array().ok_or_else(|| Self::invalid(path, "array"))?;
+        if let Some(length) = length {
+            if values.len() != length {
+                return Err(Self::invalid(path, &format!("{} elements", length)));
+            }
+        }
+        for (index, value) in values.iter().enumerate() {
+            self.encode(element, value, &format!("{}[{}]", path, index))?;
+        }
+        Ok(())
+    }
+}
+
+struct Decoder<'a> {
+    types: &'a [TypeAbi],
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize, path: &str) -> Result<&'a [u8], CodecError> {
+        match self.input.get(self.offset..self.offset + len) {
+            Some(bytes) => {
+                self.offset += len;
+                Ok(bytes)
+            }
+            None => Err(CodecError::Truncated {
+                field: path.to_string(),
+                offset: self.offset,
+                input_len: self.input.len(),
+            }),
+        }
+    }
+
+    fn invalid(&self, path: &str, offset: usize) -> CodecError {
+        CodecError::InvalidData {
+            field: path.to_string(),
+            offset,
+        }
+    }
+
+    fn decode_fields(&mut self, fields: &[FieldAbi], path: &str) -> Result<Map<String, Value>, CodecError> {
+        let mut decoded = Map::new();
+        for field in fields.iter() {
+            let field_path = field_path(path, &field.name);
+            if let Some(condition) = &field.when {
+                if !condition.holds(field_value(&decoded, &condition.field, path)?) {
+                    continue;
+                }
+            }
+            let ty = Type::parse(&field.ty)?;
+            let value = match &ty {
+                Type::Array(element, Length::Field(length)) => {
+                    let length = field_value(&decoded, length, path)? as usize;
+                    self.decode_array(element, Some(length), &field_path)?
+                }
+                ty => self.decode(ty, &field_path)?,
+            };
+            decoded.insert(field.name.clone(), value);
+        }
+        Ok(decoded)
+    }
+
+    fn decode(&mut self, ty: &Type, path: &str) -> Result<Value, CodecError> {
+        let offset = self.offset;
+        Ok(match ty {
+            Type::Unsigned(size) => {
+                let mut bytes = [0u8; 16];
+                bytes[..*size].copy_from_slice(self.take(*size, path)?);
+                let value = u128::from_le_bytes(bytes);
+                if *size <= 8 {
+                    Value::from(value as u64)
+                } else {
+                    Value::from(value.to_string())
+                }
+            }
+            Type::Signed(size) => {
+                let mut bytes = [0u8; 16];
+                bytes[..*size].copy_from_slice(self.take(*size, path)?);
+                let shift = 128 - 8 * *size as u32;
+                let value = i128::from_le_bytes(bytes) << shift >> shift;
+                if *size <= 8 {
+                    Value::from(value as i64)
+                } else {
+                    Value::from(value.to_string())
+                }
+            }
+            Type::Bool => match self.take(1, path)?[0] {
+                0 => Value::Bool(false),
+                1 => Value::Bool(true),
+                _ => return Err(self.invalid(path, offset)),
+            },
+            Type::Pubkey => {
+                let bytes: [u8; 32] = self.take(32, path)?.try_into().unwrap();
+                Value::from(Pubkey::new_from_array(bytes).to_string())
+            }
+            Type::String => {
+                let len = u32::from_le_bytes(self.take(4, path)?.try_into().unwrap()) as usize;
+                let bytes = self.take(len, path)?;
+                let value = std::str::from_utf8(bytes).map_err(|_| self.invalid(path, offset))?;
+                Value::from(value)
+            }
+            Type::Array(element, Length::Fixed(length)) => self.decode_array(element, Some(*length), path)?,
+            Type::Array(element, Length::Rest) => self.decode_array(element, None, path)?,
+            Type::Array(_, Length::Field(length)) => return Err(CodecError::MissingArgument(length.clone())),
+            Type::Option(inner) => match self.take(1, path)?[0] {
+                0 => Value::Null,
+                1 => self.decode(inner, path)?,
+                _ => return Err(self.invalid(path, offset)),
+            },
+            Type::Vec(inner) => {
+                let len = u32::from_le_bytes(self.take(4, path)?.try_into().unwrap()) as usize;
+                self.decode_array(inner, Some(len), path)?
+            }
+            Type::Named(name) => match find_type(self.types, name)? {
+                TypeKind::Struct { fields } => Value::Object(self.decode_fields(fields, path)?),
+                TypeKind::Enum { variants } => {
+                    let index = self.take(1, path)?[0] as usize;
+                    let variant = variants.get(index).ok_or_else(|| self.invalid(path, offset))?;
+                    Value::from(variant.as_str())
+                }
+            },
+        })
+    }
+
+    fn decode_array(&mut self, element: &Type, length: Option<usize>, path: &str) -> Result<Value, CodecError> {
+        let mut values = vec![];
+        while match length {
+            Some(length) => values.len() < length,
+            None => self.offset < self.input.len(),
+        } {
+            let index_path = format!("{}[{}]", path, values.len());
+            values.push(self.decode(element, &index_path)?);
+        }
+        Ok(Value::Array(values))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::{NonZeroU16, NonZeroU64};
+
+    use bonfida_bot::instruction::{create, CreateLayout, PoolInstruction};
+    use borsh::BorshSerialize;
+    use metaplex_token_metadata::{
+        instruction::{CreateMetadataAccountArgs, MetadataInstruction, UpdateMetadataAccountArgs},
+        state::{Creator, Data},
+    };
+    use program_instruction::ProgramInstruction;
+    use raydium::instruction::{AmmInstruction, SetParamsInstruction, SwapInstruction};
+    use serde_json::{json, Value};
+    use serum_dex::{
+        instruction::SelfTradeBehavior,
+        matching::{OrderType, Side},
+    };
+    use solana_program::pubkey::Pubkey;
+
+    use super::{account_labels, AbiCodec, CodecError};
+
+    fn round_trip(codec: &AbiCodec, name: &str, args: Value, expected: Vec<u8>) {
+        let data = codec.encode_data(name, &args).unwrap();
+        assert_eq!(data, expected, "{}", name);
+        let decoded = codec.decode_data_strict(&data).unwrap();
+        assert_eq!(decoded.instruction.name, name);
+        assert_eq!(Value::Object(decoded.fields), args);
+    }
+
+    #[test]
+    fn test_raydium() {
+        let codec = AbiCodec::from_json(include_str!("raydium.rs_ABI.json")).unwrap();
+        assert_eq!(codec.abi(), &AmmInstruction::abi());
+        round_trip(
+            &codec,
+            "Swap",
+            json!({"amount_in": 100, "minimum_amount_out": u64::MAX}),
+            AmmInstruction::Swap(SwapInstruction { amount_in: 100, minimum_amount_out: u64::MAX }).pack().unwrap(),
+        );
+        round_trip(&codec, "WithdrawPnl", json!({}), AmmInstruction::WithdrawPnl.pack().unwrap());
+        let owner = Pubkey::new_unique();
+        round_trip(
+            &codec,
+            "SetParams",
+            json!({"param": 10, "new_pubkey": owner.to_string()}),
+            AmmInstruction::SetParams(SetParamsInstruction { param: 10, value: None, new_pubkey: Some(owner), fees: None })
+                .pack()
+                .unwrap(),
+        );
+        round_trip(
+            &codec,
+            "SetParams",
+            json!({"param": 2, "value": 7}),
+            AmmInstruction::SetParams(SetParamsInstruction { param: 2, value: Some(7), new_pubkey: None, fees: None })
+                .pack()
+                .unwrap(),
+        );
+        assert!(matches!(
+            codec.encode_data("SetParams", &json!({"param": 10, "value": 7})),
+            Err(CodecError::MissingArgument(field)) if field == "new_pubkey"
+        ));
+        assert!(matches!(
+            codec.encode_data("Withdraw", &json!({"amount": -1})),
+            Err(CodecError::InvalidArgument { .. })
+        ));
+        assert!(matches!(codec.decode_data(&[9, 1, 2]), Err(CodecError::Truncated { .. })));
+        let decoded = codec.decode_data(&[7, 0, 0]).unwrap();
+        assert_eq!(decoded.instruction.name, "WithdrawPnl");
+        assert_eq!(decoded.trailing_len, 2);
+        assert!(matches!(
+            codec.decode_data_strict(&[7, 0, 0]),
+            Err(CodecError::TrailingBytes { expected_len: 1, trailing_len: 2 })
+        ));
+        assert_eq!(codec.decode_data_strict(&[7]).unwrap().trailing_len, 0);
+        assert!(matches!(codec.decode_data(&[42]), Err(CodecError::UnknownCode(42))));
+    }
+
+    #[test]
+    fn test_bonfida_bot() {
+        let codec = AbiCodec::from_json(include_str!("bonfida-bot.rs_ABI.json")).unwrap();
+        assert_eq!(codec.abi(), &PoolInstruction::abi());
+        let markets = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let create_instruction = PoolInstruction::Create {
+            pool_seed: [7; 32],
+            fee_collection_period: 604800,
+            fee_ratio: 150,
+            deposit_amounts: vec![1000, 2000, 3000],
+            markets: markets.clone(),
+        };
+        let args = json!({
+            "pool_seed": vec![7u8; 32],
+            "number_of_markets": 2,
+            "fee_collection_period": 604800,
+            "fee_ratio": 150,
+            "markets": markets.iter().map(|market| market.to_string()).collect::<Vec<_>>(),
+            "deposit_amounts": [1000, 2000, 3000],
+        });
+        round_trip(&codec, "Create", args.clone(), create_instruction.pack());
+        let mut v2_args = args.clone();
+        v2_args["number_of_deposits"] = json!(3);
+        round_trip(&codec, "CreateV2", v2_args, create_instruction.pack_with_layout(CreateLayout::V2).unwrap());
+
+        // Length fields are filled in from their array
+        let mut implicit_args = args.clone();
+        implicit_args.as_object_mut().unwrap().remove("number_of_markets");
+        assert_eq!(codec.encode_data("Create", &implicit_args).unwrap(), create_instruction.pack());
+        let mut wrong_args = args;
+        wrong_args["number_of_markets"] = json!(3);
+        assert!(codec.encode_data("Create", &wrong_args).is_err());
+
+        let target_mint = Pubkey::new_unique();
+        round_trip(
+            &codec,
+            "CreateOrder",
+            json!({
+                "pool_seed": vec![1u8; 32],
+                "side": "Ask",
+                "limit_price": 25,
+                "ratio_of_pool_assets_to_trade": 1000,
+                "order_type": "PostOnly",
+                "client_id": 9,
+                "self_trade_behavior": "AbortTransaction",
+                "source_index": 0,
+                "target_index": 1,
+                "market_index": 0,
+                "coin_lot_size": 100,
+                "pc_lot_size": 10,
+                "target_mint": target_mint.to_string(),
+                "serum_limit": 65535,
+            }),
+            PoolInstruction::CreateOrder {
+                pool_seed: [1; 32],
+                side: Side::Ask,
+                limit_price: NonZeroU64::new(25).unwrap(),
+                ratio_of_pool_assets_to_trade: NonZeroU16::new(1000).unwrap(),
+                order_type: OrderType::PostOnly,
+                client_id: 9,
+                self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+                source_index: 0,
+                target_index: 1,
+                market_index: 0,
+                coin_lot_size: 100,
+                pc_lot_size: 10,
+                target_mint,
+                serum_limit: 65535,
+            }
+            .pack(),
+        );
+        round_trip(
+            &codec,
+            "CancelOrder",
+            json!({"pool_seed": vec![2u8; 32], "side": "Bid", "order_id": u128::MAX.to_string()}),
+            PoolInstruction::CancelOrder { pool_seed: [2; 32], side: Side::Bid, order_id: u128::MAX }.pack(),
+        );
+    }
+
+    #[test]
+    fn test_bonfida_bot_accounts() {
+        let codec = AbiCodec::from_json(include_str!("bonfida-bot.rs_ABI.json")).unwrap();
+        let keys = (0..12).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let program_id = Pubkey::new_unique();
+        let expected = create(
+            &keys[0],
+            &keys[1],
+            &program_id,
+            &keys[2],
+            &keys[3],
+            [0; 32],
+            &vec![keys[4], keys[5]],
+            &keys[6],
+            &keys[7],
+            &vec![keys[8], keys[9]],
+            &keys[10],
+            &keys[11],
+            1,
+            1,
+            vec![1, 1],
+            vec![],
+        )
+        .unwrap();
+        let instruction = codec
+            .encode(
+                &program_id,
+                "Create",
+                &json!({
+                    "pool_seed": vec![0u8; 32],
+                    "fee_collection_period": 1,
+                    "fee_ratio": 1,
+                    "markets": [],
+                    "deposit_amounts": [1, 1],
+                }),
+                &json!({
+                    "spl_token_program_id": keys[0].to_string(),
+                    "clock_sysvar_id": keys[1].to_string(),
+                    "mint_key": keys[2].to_string(),
+                    "pool_key": keys[3].to_string(),
+                    "pool_asset_keys": [keys[4].to_string(), keys[5].to_string()],
+                    "target_pool_token_key": keys[6].to_string(),
+                    "source_owner_key": keys[7].to_string(),
+                    "source_asset_keys": [keys[8].to_string(), keys[9].to_string()],
+                    "serum_program_id": keys[10].to_string(),
+                    "signal_provider_key": keys[11].to_string(),
+                }),
+            )
+            .unwrap();
+        assert_eq!(instruction, expected);
+        assert!(matches!(
+            codec.encode_accounts("Create", &json!({})),
+            Err(CodecError::MissingAccount(name)) if name == "spl_token_program_id"
+        ));
+    }
+
+    #[test]
+    fn test_account_labels() {
+        let codec = AbiCodec::from_json(include_str!("bonfida-bot.rs_ABI.json")).unwrap();
+        let labels = account_labels(codec.instruction("Create").unwrap(), 12);
+        assert_eq!(
+            &labels[6..],
+            &[
+                "pool_key",
+                "pool_asset_keys[0]",
+                "pool_asset_keys[1]",
+                "source_owner_key",
+                "source_asset_keys[0]",
+                "source_asset_keys[1]",
+            ]
+        );
+        let create_order = codec.instruction("CreateOrder").unwrap();
+        assert_eq!(account_labels(create_order, 14).last().unwrap(), "dex_program");
+        assert_eq!(account_labels(create_order, 15).last().unwrap(), "srm_discount_account");
+        assert_eq!(account_labels(create_order, 16).last().unwrap(), "unknown[15]");
+        assert_eq!(account_labels(create_order, 2), vec!["signal_provider", "market"]);
+    }
+
+    #[test]
+    fn test_metaplex() {
+        let codec = AbiCodec::from_json(include_str!("metaplex-token-metadata.rs_ABI.json")).unwrap();
+        assert_eq!(codec.abi(), &MetadataInstruction::abi());
+        let creator = Pubkey::new_unique();
+        let data = Data {
+            name: "Solana".to_string(),
+            symbol: "SOL".to_string(),
+            uri: "https://example.com/sol.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![Creator { address: creator, verified: true, share: 100 }]),
+        };
+        let data_json = json!({
+            "name": "Solana",
+            "symbol": "SOL",
+            "uri": "https://example.com/sol.json",
+            "seller_fee_basis_points": 500,
+            "creators": [{"address": creator.to_string(), "verified": true, "share": 100}],
+        });
+        round_trip(
+            &codec,
+            "CreateMetadataAccount",
+            json!({"data": data_json, "is_mutable": true}),
+            MetadataInstruction::CreateMetadataAccount(CreateMetadataAccountArgs { data, is_mutable: true })
+                .try_to_vec()
+                .unwrap(),
+        );
+        let update_authority = Pubkey::new_unique();
+        round_trip(
+            &codec,
+            "UpdateMetadataAccount",
+            json!({"data": null, "update_authority": update_authority.to_string(), "primary_sale_happened": false}),
+            MetadataInstruction::UpdateMetadataAccount(UpdateMetadataAccountArgs {
+                data: None,
+                update_authority: Some(update_authority),
+                primary_sale_happened: Some(false),
+            })
+            .try_to_vec()
+            .unwrap(),
+        );
+        round_trip(&codec, "PuffMetadata", json!({}), MetadataInstruction::PuffMetadata.try_to_vec().unwrap());
+        assert!(matches!(codec.decode_data(&[1, 2]), Err(CodecError::InvalidData { .. })));
+    }
+}
@@ -0,0 +1,108 @@
+//! Borrowed views against the owned decoders, on the instructions indexers decode the most.
+use bonfida_bot::instruction::{
+    view::{CreateOrderView, CreateView},
+    CreateLayout, PoolInstruction,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raydium_amm::instruction::{view::SwapView, AmmInstruction, SwapInstruction};
+use serum_dex::{
+    instruction::SelfTradeBehavior,
+    matching::{OrderType, Side},
+};
+use solana_program::pubkey::Pubkey;
+use std::num::{NonZeroU16, NonZeroU64};
+
+fn swap(c: &mut Criterion) {
+    let data = AmmInstruction::Swap(SwapInstruction {
+        amount_in: 10_000,
+        minimum_amount_out: 19_554,
+    })
+    .pack()
+    .unwrap();
+    let mut group = c.benchmark_group("swap");
+    group.bench_function("unpack", |b| {
+        b.iter(|| match AmmInstruction::unpack(black_box(&data)).unwrap() {
+            AmmInstruction::Swap(swap) => (swap.amount_in, swap.minimum_amount_out),
+            _ => unreachable!(),
+        })
+    });
+    group.bench_function("view", |b| {
+        b.iter(|| {
+            let view = SwapView::new(black_box(&data)).unwrap();
+            (view.amount_in(), view.minimum_amount_out())
+        })
+    });
+    group.finish();
+}
+
+fn create_order(c: &mut Criterion) {
+    let data = PoolInstruction::CreateOrder {
+        pool_seed: [1; 32],
+        side: Side::Bid,
+        limit_price: NonZeroU64::new(23_456).unwrap(),
+        ratio_of_pool_assets_to_trade: NonZeroU16::new(18_446).unwrap(),
+        order_type: OrderType::Limit,
+        client_id: 42,
+        self_trade_behavior: SelfTradeBehavior::DecrementTake,
+        source_index: 0,
+        target_index: 1,
+        market_index: 0,
+        coin_lot_size: 100_000_000,
+        pc_lot_size: 100,
+        target_mint: Pubkey::new_unique(),
+        serum_limit: 65,
+    }
+    .pack();
+    let mut group = c.benchmark_group("create_order");
+    group.bench_function("try_unpack", |b| {
+        b.iter(|| match PoolInstruction::try_unpack(black_box(&data)).unwrap() {
+            PoolInstruction::CreateOrder {
+                limit_price,
+                ratio_of_pool_assets_to_trade,
+                ..
+            } => (limit_price, ratio_of_pool_assets_to_trade),
+            _ => unreachable!(),
+        })
+    });
+    group.bench_function("view", |b| {
+        b.iter(|| {
+            let view = CreateOrderView::new(black_box(&data)).unwrap();
+            (view.limit_price(), view.ratio_of_pool_assets_to_trade())
+        })
+    });
+    group.finish();
+}
+
+fn create(c: &mut Criterion) {
+    let instruction = PoolInstruction::Create {
+        pool_seed: [2; 32],
+        fee_collection_period: 604_800,
+        fee_ratio: 150,
+        deposit_amounts: (0..10).map(|i| 1_000 * i).collect(),
+        markets: (0..10).map(|_| Pubkey::new_unique()).collect(),
+    };
+    for layout in [CreateLayout::Legacy, CreateLayout::V2].iter() {
+        let data = instruction.pack_with_layout(*layout).unwrap();
+        let mut group = c.benchmark_group(format!("create_{:?}", layout).to_lowercase());
+        group.bench_function("try_unpack", |b| {
+            b.iter(|| match PoolInstruction::try_unpack(black_box(&data)).unwrap() {
+                PoolInstruction::Create {
+                    markets,
+                    deposit_amounts,
+                    ..
+                } => (markets.len(), deposit_amounts.iter().sum::<u64>()),
+                _ => unreachable!(),
+            })
+        });
+        group.bench_function("view", |b| {
+            b.iter(|| {
+                let view = CreateView::new(black_box(&data)).unwrap();
+                (view.markets().len(), view.deposit_amounts().sum::<u64>())
+            })
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, swap, create_order, create);
+criterion_main!(benches);
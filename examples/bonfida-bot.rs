@@ -1,5 +1,9 @@
 use crate::error::BonfidaBotError;
 use crate::state::{BONFIDA_BNB, BONFIDA_FEE};
+use program_instruction::{
+    instruction_layouts, AccountAbi, AccountCount, FieldAbi, FieldCodec, InstructionAbi, InstructionLayout, LayoutError,
+    ProgramAbi, ProgramInstruction, TypeAbi,
+};
 use serum_dex::{
     instruction::SelfTradeBehavior,
     matching::{OrderType, Side},
@@ -11,15 +15,21 @@ use solana_program::{
     pubkey::Pubkey,
 };
 use spl_associated_token_account::get_associated_token_address;
+#[cfg(any(test, feature = "serde"))]
+use serde::{Deserialize, Serialize};
 use std::{
     convert::TryInto,
+    fmt,
     mem::size_of,
     num::{NonZeroU16, NonZeroU64},
+    ops::Range,
     str::FromStr,
 };
 
+/// With the `serde` feature it is represented as `{"instruction": "Deposit", "args": {...}}`.
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize), serde(tag = "instruction", content = "args"))]
 pub enum PoolInstruction {
     /// Initializes an empty pool account for the bonfida-bot program
     ///
@@ -60,6 +70,10 @@ pub enum PoolInstruction {
     ///      corresponding PoolAssets in the pool account data.
     ///   M+5. `[signer]` The source owner account
     ///   M+6..2M+6. `[writable]` The M source token accounts in the same order as above
+    ///
+    /// Encoded with tag 1 in the legacy layout, or tag 8 when the deposit amounts
+    /// are prefixed by their count (see [CreateLayout](enum.CreateLayout.html)).
+    /// The decoded variant doesn't keep the layout, its `discriminant` is always 1.
     Create {
         pool_seed: [u8; 32],
         fee_collection_period: u64,
@@ -115,11 +129,14 @@ pub enum PoolInstruction {
     ///   14. `[writable]` (optional) The (M)SRM discount account
     CreateOrder {
         pool_seed: [u8; 32],
+        #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_serum::SideDef"))]
         side: Side,
         limit_price: NonZeroU64,
         ratio_of_pool_assets_to_trade: NonZeroU16,
+        #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_serum::OrderTypeDef"))]
         order_type: OrderType,
         client_id: u64,
+        #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_serum::SelfTradeBehaviorDef"))]
         self_trade_behavior: SelfTradeBehavior,
         source_index: u64,
         target_index: u64,
@@ -144,7 +161,9 @@ pub enum PoolInstruction {
     ///    7. `[]` The dex program account
     CancelOrder {
         pool_seed: [u8; 32],
+        #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_serum::SideDef"))]
         side: Side,
+        #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_serum::order_id"))]
         order_id: u128,
     },
     /// A permissionless crank to settle funds out of one of the pool's active OpenOrders accounts.
@@ -204,395 +223,1025 @@ pub enum PoolInstruction {
     CollectFees { pool_seed: [u8; 32] },
 }
 
+/// Serde support for the serum enums, which don't implement it.
+#[cfg(any(test, feature = "serde"))]
+mod serde_serum {
+    use serde::{Deserialize, Serialize};
+    use serum_dex::{
+        instruction::SelfTradeBehavior,
+        matching::{OrderType, Side},
+    };
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Side")]
+    pub enum SideDef {
+        Bid,
+        Ask,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "OrderType")]
+    pub enum OrderTypeDef {
+        Limit,
+        ImmediateOrCancel,
+        PostOnly,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "SelfTradeBehavior")]
+    pub enum SelfTradeBehaviorDef {
+        DecrementTake,
+        CancelProvide,
+        AbortTransaction,
+    }
+
+    /// Order ids are written as decimal strings, JSON numbers can't hold a u128
+    pub mod order_id {
+        use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(order_id: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(order_id)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+            String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+        }
+    }
+}
+
+/// Tag of a `Create` instruction encoded with [CreateLayout::V2](enum.CreateLayout.html#variant.V2).
+pub const CREATE_V2_TAG: u8 = 8;
+
+/// Wire layout of a `Create` instruction. Both are accepted by `unpack`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CreateLayout {
+    /// Tag 1, every byte after the markets is a deposit amount
+    Legacy,
+    /// Tag 8, the deposit amounts are prefixed by their u16 count and end the payload
+    V2,
+}
+
+/// Reason why instruction data could not be decoded into a [PoolInstruction](enum.PoolInstruction.html).
+/// Byte ranges are offsets into the whole instruction data, tag included.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UnpackError {
+    /// The instruction data is empty
+    EmptyInput,
+    /// The tag does not match any instruction
+    UnknownTag(u8),
+    /// The instruction data ends before the field
+    Truncated {
+        tag: u8,
+        field: &'static str,
+        range: Range<usize>,
+        input_len: usize,
+    },
+    /// The instruction data goes on for `trailing_len` bytes after its last field
+    TrailingBytes {
+        tag: u8,
+        expected_len: usize,
+        trailing_len: usize,
+    },
+    /// The bytes of the field don't hold a valid value
+    InvalidValue {
+        tag: u8,
+        field: &'static str,
+        range: Range<usize>,
+        input_len: usize,
+    },
+}
+
+impl fmt::Display for UnpackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EmptyInput => write!(f, "Empty instruction data"),
+            Self::UnknownTag(tag) => write!(f, "Unsupported tag {}", tag),
+            Self::Truncated {
+                tag,
+                field,
+                range,
+                input_len,
+            } => write!(
+                f,
+                "Tag {}: {} expected at bytes {}..{}, input is {} bytes long",
+                tag, field, range.start, range.end, input_len
+            ),
+            Self::TrailingBytes {
+                tag,
+                expected_len,
+                trailing_len,
+            } => write!(
+                f,
+                "Tag {}: {} bytes after the last field, expected {} bytes",
+                tag, trailing_len, expected_len
+            ),
+            Self::InvalidValue {
+                tag,
+                field,
+                range,
+                input_len,
+            } => write!(
+                f,
+                "Tag {}: invalid {} at bytes {}..{} of {}",
+                tag, field, range.start, range.end, input_len
+            ),
+        }
+    }
+}
+
+/// Reason why a [PoolInstruction](enum.PoolInstruction.html) could not be encoded
+#[derive(Clone, Debug, PartialEq)]
+pub enum PackError {
+    /// The list has more entries than its u16 count can hold
+    TooManyEntries { field: &'static str, len: usize },
+}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooManyEntries { field, len } => {
+                write!(f, "{} has {} entries, at most {} can be encoded", field, len, u16::MAX)
+            }
+        }
+    }
+}
+
+impl From<PackError> for ProgramError {
+    fn from(_: PackError) -> Self {
+        ProgramError::InvalidArgument
+    }
+}
+
+/// Count of a list encoded as a u16
+fn entry_count(field: &'static str, len: usize) -> Result<u16, PackError> {
+    if len > u16::MAX as usize {
+        return Err(PackError::TooManyEntries { field, len });
+    }
+    Ok(len as u16)
+}
+
+impl From<UnpackError> for ProgramError {
+    fn from(_: UnpackError) -> Self {
+        BonfidaBotError::InvalidInstruction.into()
+    }
+}
+
+/// Reads the fields following the tag, ranges being relative to the end of the tag.
+struct PayloadReader<'a> {
+    tag: u8,
+    rest: &'a [u8],
+    input_len: usize,
+}
+
+impl<'a> PayloadReader<'a> {
+    fn truncated(&self, field: &'static str, range: Range<usize>) -> UnpackError {
+        UnpackError::Truncated {
+            tag: self.tag,
+            field,
+            range: range.start + 1..range.end + 1,
+            input_len: self.input_len,
+        }
+    }
+
+    /// Converts the error of a fixed field, whose range already counts the tag
+    fn layout_error(&self, err: LayoutError) -> UnpackError {
+        match err {
+            LayoutError::Truncated { field, range } => UnpackError::Truncated {
+                tag: self.tag,
+                field,
+                range,
+                input_len: self.input_len,
+            },
+            LayoutError::Invalid { field, range } => UnpackError::InvalidValue {
+                tag: self.tag,
+                field,
+                range,
+                input_len: self.input_len,
+            },
+        }
+    }
+
+    fn expect_end(&self, end: usize) -> Result<(), UnpackError> {
+        if self.rest.len() > end {
+            return Err(UnpackError::TrailingBytes {
+                tag: self.tag,
+                expected_len: end + 1,
+                trailing_len: self.rest.len() - end,
+            });
+        }
+        Ok(())
+    }
+
+    fn bytes(&self, field: &'static str, range: Range<usize>) -> Result<&'a [u8], UnpackError> {
+        self.rest
+            .get(range.clone())
+            .ok_or_else(|| self.truncated(field, range))
+    }
+
+    fn u16(&self, field: &'static str, range: Range<usize>) -> Result<u16, UnpackError> {
+        self.bytes(field, range.clone())?
+            .try_into()
+            .map(u16::from_le_bytes)
+            .map_err(|_| self.truncated(field, range))
+    }
+}
+
+/// Serum enums, encoded as their u8 discriminant
+struct SideField;
+struct OrderTypeField;
+struct SelfTradeBehaviorField;
+
+impl FieldCodec for SideField {
+    type Value = Side;
+    const SIZE: usize = 1;
+    const ABI_TYPE: &'static str = "Side";
+
+    fn decode(bytes: &[u8]) -> Option<Side> {
+        match bytes {
+            [0] => Some(Side::Bid),
+            [1] => Some(Side::Ask),
+            _ => None,
+        }
+    }
+
+    fn encode(value: &Side, buf: &mut Vec<u8>) {
+        buf.push(*value as u8);
+    }
+}
+
+impl FieldCodec for OrderTypeField {
+    type Value = OrderType;
+    const SIZE: usize = 1;
+    const ABI_TYPE: &'static str = "OrderType";
+
+    fn decode(bytes: &[u8]) -> Option<OrderType> {
+        match bytes {
+            [0] => Some(OrderType::Limit),
+            [1] => Some(OrderType::ImmediateOrCancel),
+            [2] => Some(OrderType::PostOnly),
+            _ => None,
+        }
+    }
+
+    fn encode(value: &OrderType, buf: &mut Vec<u8>) {
+        buf.push(*value as u8);
+    }
+}
+
+impl FieldCodec for SelfTradeBehaviorField {
+    type Value = SelfTradeBehavior;
+    const SIZE: usize = 1;
+    const ABI_TYPE: &'static str = "SelfTradeBehavior";
+
+    fn decode(bytes: &[u8]) -> Option<SelfTradeBehavior> {
+        match bytes {
+            [0] => Some(SelfTradeBehavior::DecrementTake),
+            [1] => Some(SelfTradeBehavior::CancelProvide),
+            [2] => Some(SelfTradeBehavior::AbortTransaction),
+            _ => None,
+        }
+    }
+
+    fn encode(value: &SelfTradeBehavior, buf: &mut Vec<u8>) {
+        buf.push(*value as u8);
+    }
+}
+
+instruction_layouts! {
+    impl PoolInstruction {
+        fixed {
+            0 => Init { pool_seed: [u8; 32], max_number_of_assets: u32, number_of_markets: u16 },
+            2 => Deposit { pool_seed: [u8; 32], pool_token_amount: u64 },
+            3 => CreateOrder {
+                pool_seed: [u8; 32],
+                side: SideField,
+                limit_price: NonZeroU64,
+                ratio_of_pool_assets_to_trade: NonZeroU16,
+                order_type: OrderTypeField,
+                client_id: u64,
+                self_trade_behavior: SelfTradeBehaviorField,
+                source_index: u64,
+                target_index: u64,
+                market_index: u16,
+                coin_lot_size: u64,
+                pc_lot_size: u64,
+                target_mint: Pubkey,
+                serum_limit: u16,
+            },
+            4 => CancelOrder { pool_seed: [u8; 32], side: SideField, order_id: u128 },
+            5 => SettleFunds { pool_seed: [u8; 32], pc_index: u64, coin_index: u64 },
+            6 => Redeem { pool_seed: [u8; 32], pool_token_amount: u64 },
+            7 => CollectFees { pool_seed: [u8; 32] },
+        }
+        head {
+            1 => Create { pool_seed: [u8; 32], number_of_markets: u16, fee_collection_period: u64, fee_ratio: u16 },
+            8 => Create { pool_seed: [u8; 32], number_of_markets: u16, fee_collection_period: u64, fee_ratio: u16 },
+        }
+    }
+}
+
 impl PoolInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        use BonfidaBotError::InvalidInstruction;
-        let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
-        Ok(match tag {
-            0 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let max_number_of_assets: u32 = rest
-                    .get(32..36)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u32::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let number_of_markets: u16 = rest
-                    .get(36..38)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u16::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Self::Init {
-                    pool_seed,
-                    max_number_of_assets,
-                    number_of_markets,
-                }
+        Self::try_unpack(input).map_err(|e| {
+            msg!("{}", e);
+            e.into()
+        })
+    }
+
+    /// Decodes instruction data without ever panicking, reporting which field of which
+    /// tag could not be read.
+    pub fn try_unpack(input: &[u8]) -> Result<Self, UnpackError> {
+        let (&tag, rest) = input.split_first().ok_or(UnpackError::EmptyInput)?;
+        let reader = PayloadReader {
+            tag,
+            rest,
+            input_len: input.len(),
+        };
+        if let Some(instruction) = Self::unpack_fixed(input).map_err(|e| reader.layout_error(e))? {
+            return Ok(instruction);
+        }
+        match tag {
+            1 | CREATE_V2_TAG => view::CreateView::new(input).map(|view| view.instruction()),
+            _ => Err(UnpackError::UnknownTag(tag)),
+        }
+    }
+
+    /// Like [try_unpack](#method.try_unpack) but fails with `TrailingBytes` when the data goes
+    /// on after the last field of a fixed layout, so that it packs back to the same bytes.
+    /// Both `Create` layouts already take every byte.
+    pub fn try_unpack_strict(input: &[u8]) -> Result<Self, UnpackError> {
+        let instruction = Self::try_unpack(input)?;
+        match Self::layout(input[0]) {
+            Some(layout) if !layout.tail && input.len() > layout.size() => Err(UnpackError::TrailingBytes {
+                tag: input[0],
+                expected_len: layout.size(),
+                trailing_len: input.len() - layout.size(),
+            }),
+            _ => Ok(instruction),
+        }
+    }
+
+    /// Packs the instruction, a `Create` in the legacy layout.
+    ///
+    /// Panics when a `Create` has more than `u16::MAX` markets, `pack_with_layout`
+    /// reports it as an error.
+    pub fn pack(&self) -> Vec<u8> {
+        self.pack_with_layout(CreateLayout::Legacy)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Packs the instruction, encoding a `Create` with the given layout. Fails when
+    /// its markets or, in the v2 layout, its deposit amounts don't fit their u16 count.
+    pub fn pack_with_layout(&self, layout: CreateLayout) -> Result<Vec<u8>, PackError> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        if let Self::Create {
+            pool_seed,
+            fee_collection_period,
+            fee_ratio,
+            deposit_amounts,
+            markets,
+        } = self
+        {
+            buf.push(match layout {
+                CreateLayout::Legacy => 1,
+                CreateLayout::V2 => CREATE_V2_TAG,
+            });
+            buf.extend_from_slice(pool_seed);
+            buf.extend_from_slice(&entry_count("markets", markets.len())?.to_le_bytes());
+            buf.extend_from_slice(&fee_collection_period.to_le_bytes());
+            buf.extend_from_slice(&fee_ratio.to_le_bytes());
+            for market in markets {
+                buf.extend_from_slice(&market.to_bytes())
             }
-            1 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let number_of_markets = rest
-                    .get(32..34)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u16::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let fee_collection_period = rest
-                    .get(34..42)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let fee_ratio = rest
-                    .get(42..44)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u16::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let mut markets = Vec::with_capacity(number_of_markets as usize);
-                let mut offset = 44;
-                for _ in 0..number_of_markets {
-                    markets.push(
-                        rest.get(offset..offset + 32)
-                            .and_then(|slice| slice.try_into().ok())
-                            .map(Pubkey::new)
-                            .ok_or(InvalidInstruction)?,
-                    );
-                    offset = offset + 32;
-                }
-                let mut k = offset;
-                let mut deposit_amounts = vec![];
-                while k != 0 {
-                    match rest.get(k..(k + 8)) {
-                        None => k = 0,
-                        Some(bytes) => {
-                            deposit_amounts.push(u64::from_le_bytes(bytes.try_into().unwrap()));
-                            k = k + 8;
-                        }
-                    }
-                }
-                Self::Create {
-                    pool_seed,
-                    markets,
-                    deposit_amounts,
-                    fee_collection_period,
-                    fee_ratio,
-                }
+            if layout == CreateLayout::V2 {
+                buf.extend_from_slice(&entry_count("deposit_amounts", deposit_amounts.len())?.to_le_bytes());
+            }
+            for amount in deposit_amounts.iter() {
+                buf.extend_from_slice(&amount.to_le_bytes());
             }
-            2 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let pool_token_amount = rest
-                    .get(32..40)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Self::Deposit {
-                    pool_seed,
-                    pool_token_amount,
+        } else {
+            self.pack_fixed(&mut buf);
+        }
+        Ok(buf)
+    }
+}
+
+impl ProgramInstruction for PoolInstruction {
+    /// Tag of the variant, 1 for a `Create` even when it was decoded from `CREATE_V2_TAG`
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::Init { .. } => 0,
+            Self::Create { .. } => 1,
+            Self::Deposit { .. } => 2,
+            Self::CreateOrder { .. } => 3,
+            Self::CancelOrder { .. } => 4,
+            Self::SettleFunds { .. } => 5,
+            Self::Redeem { .. } => 6,
+            Self::CollectFees { .. } => 7,
+        }
+    }
+
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Init { .. } => "Init",
+            Self::Create { .. } => "Create",
+            Self::Deposit { .. } => "Deposit",
+            Self::CreateOrder { .. } => "CreateOrder",
+            Self::CancelOrder { .. } => "CancelOrder",
+            Self::SettleFunds { .. } => "SettleFunds",
+            Self::Redeem { .. } => "Redeem",
+            Self::CollectFees { .. } => "CollectFees",
+        }
+    }
+
+    fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+        Ok(self.pack_with_layout(CreateLayout::Legacy)?)
+    }
+
+    fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        PoolInstruction::unpack(input)
+    }
+
+    fn expected_account_count(&self) -> AccountCount {
+        match self {
+            Self::Init { .. } => AccountCount::exactly(6),
+            // One pool and one source account per deposited asset
+            Self::Create {
+                deposit_amounts, ..
+            } => AccountCount::exactly(8 + 2 * deposit_amounts.len()),
+            // The number of pool assets is only known from the pool account data
+            Self::Deposit { .. } => AccountCount::at_least(8),
+            Self::CreateOrder { .. } => AccountCount::between(14, 15),
+            Self::CancelOrder { .. } => AccountCount::exactly(8),
+            Self::SettleFunds { .. } => AccountCount::between(11, 12),
+            Self::Redeem { .. } => AccountCount::at_least(6),
+            Self::CollectFees { .. } => AccountCount::exactly(7),
+        }
+    }
+
+    fn abi() -> ProgramAbi {
+        // The fixed fields come from the layouts, only the tails of `Create` are described here
+        let inputs = |tag: u8| InstructionLayout::find(Self::LAYOUTS, tag).abi_inputs();
+        let create_inputs = |layout: CreateLayout| {
+            let mut inputs = inputs(match layout {
+                CreateLayout::Legacy => 1,
+                CreateLayout::V2 => CREATE_V2_TAG,
+            });
+            inputs.push(FieldAbi::new("markets", "Pubkey;number_of_markets"));
+            match layout {
+                CreateLayout::Legacy => inputs.push(FieldAbi::new("deposit_amounts", "u64;..")),
+                CreateLayout::V2 => {
+                    inputs.push(FieldAbi::new("number_of_deposits", "u16"));
+                    inputs.push(FieldAbi::new("deposit_amounts", "u64;number_of_deposits"));
                 }
             }
-            3 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .ok_or(InvalidInstruction)?;
-                let side = match rest.get(32).ok_or(InvalidInstruction)? {
-                    0 => Side::Bid,
-                    1 => Side::Ask,
-                    _ => return Err(InvalidInstruction.into()),
-                };
-                let limit_price = NonZeroU64::new(
-                    rest.get(33..41)
-                        .and_then(|slice| slice.try_into().ok())
-                        .map(u64::from_le_bytes)
-                        .ok_or(InvalidInstruction)?,
+            inputs
+        };
+        let create_accounts = vec![
+            AccountAbi::readonly("spl_token_program_id"),
+            AccountAbi::readonly("clock_sysvar_id"),
+            AccountAbi::readonly("serum_program_id"),
+            AccountAbi::readonly("signal_provider_key"),
+            AccountAbi::writable("mint_key"),
+            AccountAbi::writable("target_pool_token_key"),
+            AccountAbi::writable("pool_key"),
+            AccountAbi::writable("pool_asset_keys").repeated(),
+            AccountAbi::readonly("source_owner_key").signer(),
+            AccountAbi::writable("source_asset_keys").repeated(),
+        ];
+        ProgramAbi {
+            instructions: vec![
+                InstructionAbi::new(
+                    0,
+                    "Init",
+                    inputs(0),
+                    vec![
+                        AccountAbi::readonly("system_program_id"),
+                        AccountAbi::readonly("rent_program_id"),
+                        AccountAbi::readonly("spl_token_program_id"),
+                        AccountAbi::writable("pool_key"),
+                        AccountAbi::writable("mint_key"),
+                        AccountAbi::writable("payer_key").signer(),
+                    ],
                 )
-                .ok_or(InvalidInstruction)?;
-                let ratio_of_pool_assets_to_trade = NonZeroU16::new(
-                    rest.get(41..43)
-                        .and_then(|slice| slice.try_into().ok())
-                        .map(u16::from_le_bytes)
-                        .ok_or(InvalidInstruction)?,
+                .op_name("init"),
+                InstructionAbi::new(1, "Create", create_inputs(CreateLayout::Legacy), create_accounts.clone())
+                    .op_name("create"),
+                InstructionAbi::new(
+                    2,
+                    "Deposit",
+                    inputs(2),
+                    vec![
+                        AccountAbi::readonly("spl_token_program_id"),
+                        AccountAbi::writable("mint_key"),
+                        AccountAbi::writable("target_pool_token_key"),
+                        AccountAbi::writable("signal_provider_pool_token_key"),
+                        AccountAbi::writable("bonfida_fee_pt_account"),
+                        AccountAbi::writable("bonfida_bnb_pt_account"),
+                        AccountAbi::readonly("pool_key"),
+                        AccountAbi::writable("pool_asset_keys").repeated(),
+                        AccountAbi::readonly("source_owner").signer(),
+                        AccountAbi::writable("source_asset_keys").repeated(),
+                    ],
                 )
-                .ok_or(InvalidInstruction)?;
-
-                let order_type = match rest.get(43).ok_or(InvalidInstruction)? {
-                    0 => OrderType::Limit,
-                    1 => OrderType::ImmediateOrCancel,
-                    2 => OrderType::PostOnly,
-                    _ => return Err(InvalidInstruction.into()),
-                };
-                let client_id = rest
-                    .get(44..52)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let self_trade_behavior = match rest.get(52).ok_or(InvalidInstruction)? {
-                    0 => SelfTradeBehavior::DecrementTake,
-                    1 => SelfTradeBehavior::CancelProvide,
-                    2 => SelfTradeBehavior::AbortTransaction,
-                    _ => return Err(InvalidInstruction.into()),
-                };
-                let source_index = rest
-                    .get(53..61)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let target_index = rest
-                    .get(61..69)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let market_index = rest
-                    .get(69..71)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u16::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let coin_lot_size = rest
-                    .get(71..79)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let pc_lot_size = rest
-                    .get(79..87)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let target_mint = rest
-                    .get(87..119)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(Pubkey::new)
-                    .ok_or(InvalidInstruction)?;
-                let serum_limit = rest
-                    .get(119..121)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u16::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Self::CreateOrder {
-                    pool_seed,
-                    side,
-                    limit_price,
-                    ratio_of_pool_assets_to_trade,
-                    order_type,
-                    client_id,
-                    self_trade_behavior,
-                    source_index,
-                    target_index,
-                    market_index,
-                    coin_lot_size,
-                    pc_lot_size,
-                    target_mint,
-                    serum_limit
-                }
-            }
-            4 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let side = match rest.get(32).ok_or(InvalidInstruction)? {
-                    0 => Side::Bid,
-                    1 => Side::Ask,
-                    _ => return Err(InvalidInstruction.into()),
-                };
-                let order_id = rest
-                    .get(33..49)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u128::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-
-                Self::CancelOrder {
-                    pool_seed,
-                    side,
-                    order_id,
-                }
+                .op_name("deposit"),
+                InstructionAbi::new(
+                    3,
+                    "CreateOrder",
+                    inputs(3),
+                    vec![
+                        AccountAbi::readonly("signal_provider").signer(),
+                        AccountAbi::writable("market"),
+                        AccountAbi::writable("payer_pool_asset_account"),
+                        AccountAbi::writable("openorders_account"),
+                        AccountAbi::writable("serum_event_queue"),
+                        AccountAbi::writable("serum_request_queue"),
+                        AccountAbi::writable("serum_market_bids"),
+                        AccountAbi::writable("serum_market_asks"),
+                        AccountAbi::writable("pool_account"),
+                        AccountAbi::writable("coin_vault"),
+                        AccountAbi::writable("pc_vault"),
+                        AccountAbi::readonly("spl_token_program"),
+                        AccountAbi::readonly("rent_sysvar"),
+                        AccountAbi::readonly("dex_program"),
+                        AccountAbi::writable("srm_discount_account").optional(),
+                    ],
+                )
+                .op_name("create_order"),
+                InstructionAbi::new(
+                    4,
+                    "CancelOrder",
+                    inputs(4),
+                    vec![
+                        AccountAbi::readonly("signal_provider").signer(),
+                        AccountAbi::readonly("market"),
+                        AccountAbi::writable("openorders_account"),
+                        AccountAbi::writable("serum_market_bids"),
+                        AccountAbi::writable("serum_market_asks"),
+                        AccountAbi::writable("serum_event_queue"),
+                        AccountAbi::readonly("pool_account"),
+                        AccountAbi::readonly("dex_program"),
+                    ],
+                )
+                .op_name("cancel_order"),
+                InstructionAbi::new(
+                    5,
+                    "SettleFunds",
+                    inputs(5),
+                    vec![
+                        AccountAbi::writable("market"),
+                        AccountAbi::writable("openorders_account"),
+                        AccountAbi::writable("pool_account"),
+                        AccountAbi::readonly("pool_token_mint"),
+                        AccountAbi::writable("coin_vault"),
+                        AccountAbi::writable("pc_vault"),
+                        AccountAbi::writable("pool_coin_wallet"),
+                        AccountAbi::writable("pool_pc_wallet"),
+                        AccountAbi::readonly("vault_signer"),
+                        AccountAbi::readonly("spl_token_program"),
+                        AccountAbi::readonly("dex_program"),
+                        AccountAbi::writable("referrer_pc_account").optional(),
+                    ],
+                )
+                .op_name("settle_funds"),
+                InstructionAbi::new(
+                    6,
+                    "Redeem",
+                    inputs(6),
+                    vec![
+                        AccountAbi::readonly("spl_token_program_id"),
+                        AccountAbi::readonly("clock_sysvar_id"),
+                        AccountAbi::writable("mint_key"),
+                        AccountAbi::readonly("source_pool_token_owner_key").signer(),
+                        AccountAbi::writable("source_pool_token_key"),
+                        AccountAbi::writable("pool_key"),
+                        AccountAbi::writable("pool_asset_keys").repeated(),
+                        AccountAbi::writable("target_asset_keys").repeated(),
+                    ],
+                )
+                .op_name("redeem"),
+                InstructionAbi::new(
+                    7,
+                    "CollectFees",
+                    inputs(7),
+                    vec![
+                        AccountAbi::readonly("spl_token_program_id"),
+                        AccountAbi::readonly("clock_sysvar_id"),
+                        AccountAbi::writable("pool_key"),
+                        AccountAbi::writable("pool_token_mint"),
+                        AccountAbi::writable("signal_provider_pool_token_key"),
+                        AccountAbi::writable("bonfida_fee_pt_account"),
+                        AccountAbi::writable("bonfida_bnb_pt_account"),
+                    ],
+                )
+                .op_name("collect_fees"),
+                // Built by `create` too, with the data packed with `CreateLayout::V2`
+                InstructionAbi::new(CREATE_V2_TAG, "CreateV2", create_inputs(CreateLayout::V2), create_accounts)
+                    .op_name("create"),
+            ],
+            types: vec![
+                TypeAbi::new_enum("Side", &["Bid", "Ask"]),
+                TypeAbi::new_enum("OrderType", &["Limit", "ImmediateOrCancel", "PostOnly"]),
+                TypeAbi::new_enum(
+                    "SelfTradeBehavior",
+                    &["DecrementTake", "CancelProvide", "AbortTransaction"],
+                ),
+            ],
+        }
+    }
+}
+
+/// Borrowed views of the instructions indexers decode the most. `new` checks the data once,
+/// failing with the errors of [PoolInstruction::try_unpack](../enum.PoolInstruction.html#method.try_unpack),
+/// then each field is read from the data when asked, without copying it.
+pub mod view {
+    use super::{
+        CreateLayout, OrderTypeField, PayloadReader, PoolInstruction, SelfTradeBehaviorField, SideField, UnpackError,
+        CREATE_V2_TAG,
+    };
+    use program_instruction::{FieldCodec, InstructionLayout, LayoutError};
+    use serum_dex::{
+        instruction::SelfTradeBehavior,
+        matching::{OrderType, Side},
+    };
+    use solana_program::pubkey::Pubkey;
+    use std::{
+        convert::TryInto,
+        num::{NonZeroU16, NonZeroU64},
+        ops::Range,
+    };
+
+    const CREATE_TAG: u8 = 1;
+    const CREATE_ORDER_TAG: u8 = 3;
+
+    const CREATE: &InstructionLayout = InstructionLayout::find(PoolInstruction::LAYOUTS, CREATE_TAG);
+    const CREATE_ORDER: &InstructionLayout = InstructionLayout::find(PoolInstruction::LAYOUTS, CREATE_ORDER_TAG);
+
+    // Byte ranges of the fields, tag included, a field missing from the layouts fails the build
+    const POOL_SEED: Range<usize> = CREATE_ORDER.field_range("pool_seed");
+    const NUMBER_OF_MARKETS: Range<usize> = CREATE.field_range("number_of_markets");
+    const FEE_COLLECTION_PERIOD: Range<usize> = CREATE.field_range("fee_collection_period");
+    const FEE_RATIO: Range<usize> = CREATE.field_range("fee_ratio");
+    const SIDE: Range<usize> = CREATE_ORDER.field_range("side");
+    const LIMIT_PRICE: Range<usize> = CREATE_ORDER.field_range("limit_price");
+    const RATIO_OF_POOL_ASSETS_TO_TRADE: Range<usize> = CREATE_ORDER.field_range("ratio_of_pool_assets_to_trade");
+    const ORDER_TYPE: Range<usize> = CREATE_ORDER.field_range("order_type");
+    const CLIENT_ID: Range<usize> = CREATE_ORDER.field_range("client_id");
+    const SELF_TRADE_BEHAVIOR: Range<usize> = CREATE_ORDER.field_range("self_trade_behavior");
+    const SOURCE_INDEX: Range<usize> = CREATE_ORDER.field_range("source_index");
+    const TARGET_INDEX: Range<usize> = CREATE_ORDER.field_range("target_index");
+    const MARKET_INDEX: Range<usize> = CREATE_ORDER.field_range("market_index");
+    const COIN_LOT_SIZE: Range<usize> = CREATE_ORDER.field_range("coin_lot_size");
+    const PC_LOT_SIZE: Range<usize> = CREATE_ORDER.field_range("pc_lot_size");
+    const TARGET_MINT: Range<usize> = CREATE_ORDER.field_range("target_mint");
+    const SERUM_LIMIT: Range<usize> = CREATE_ORDER.field_range("serum_limit");
+    const CREATE_ORDER_LEN: usize = CREATE_ORDER.size();
+
+    fn read<C: FieldCodec>(data: &[u8], range: Range<usize>) -> C::Value {
+        // Only called on ranges `new` has checked
+        match C::decode(&data[range]) {
+            Some(value) => value,
+            None => unreachable!(),
+        }
+    }
+
+    fn pool_seed(data: &[u8]) -> &[u8; 32] {
+        data[POOL_SEED].try_into().unwrap()
+    }
+
+    /// Error of data `new` rejected, data decoding as another instruction fails with `UnknownTag`
+    fn not_a(data: &[u8]) -> UnpackError {
+        match PoolInstruction::try_unpack(data) {
+            Err(err) => err,
+            Ok(_) => UnpackError::UnknownTag(data[0]),
+        }
+    }
+
+    /// [PoolInstruction::CreateOrder](../enum.PoolInstruction.html#variant.CreateOrder) data,
+    /// the bytes after its last field are ignored as by `try_unpack`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct CreateOrderView<'a> {
+        data: &'a [u8],
+    }
+
+    impl<'a> CreateOrderView<'a> {
+        pub fn new(data: &'a [u8]) -> Result<Self, UnpackError> {
+            let valid = data.first() == Some(&CREATE_ORDER_TAG)
+                && data.len() >= CREATE_ORDER_LEN
+                && SideField::decode(&data[SIDE]).is_some()
+                && NonZeroU64::decode(&data[LIMIT_PRICE]).is_some()
+                && NonZeroU16::decode(&data[RATIO_OF_POOL_ASSETS_TO_TRADE]).is_some()
+                && OrderTypeField::decode(&data[ORDER_TYPE]).is_some()
+                && SelfTradeBehaviorField::decode(&data[SELF_TRADE_BEHAVIOR]).is_some();
+            if !valid {
+                return Err(not_a(data));
             }
-            5 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let pc_index = rest
-                    .get(32..40)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                let coin_index = rest
-                    .get(40..48)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Self::SettleFunds {
-                    pool_seed,
-                    pc_index,
-                    coin_index,
-                }
+            Ok(Self { data })
+        }
+
+        pub fn pool_seed(&self) -> &'a [u8; 32] {
+            pool_seed(self.data)
+        }
+
+        pub fn side(&self) -> Side {
+            read::<SideField>(self.data, SIDE)
+        }
+
+        pub fn limit_price(&self) -> NonZeroU64 {
+            read::<NonZeroU64>(self.data, LIMIT_PRICE)
+        }
+
+        pub fn ratio_of_pool_assets_to_trade(&self) -> NonZeroU16 {
+            read::<NonZeroU16>(self.data, RATIO_OF_POOL_ASSETS_TO_TRADE)
+        }
+
+        pub fn order_type(&self) -> OrderType {
+            read::<OrderTypeField>(self.data, ORDER_TYPE)
+        }
+
+        pub fn client_id(&self) -> u64 {
+            read::<u64>(self.data, CLIENT_ID)
+        }
+
+        pub fn self_trade_behavior(&self) -> SelfTradeBehavior {
+            read::<SelfTradeBehaviorField>(self.data, SELF_TRADE_BEHAVIOR)
+        }
+
+        pub fn source_index(&self) -> u64 {
+            read::<u64>(self.data, SOURCE_INDEX)
+        }
+
+        pub fn target_index(&self) -> u64 {
+            read::<u64>(self.data, TARGET_INDEX)
+        }
+
+        pub fn market_index(&self) -> u16 {
+            read::<u16>(self.data, MARKET_INDEX)
+        }
+
+        pub fn coin_lot_size(&self) -> u64 {
+            read::<u64>(self.data, COIN_LOT_SIZE)
+        }
+
+        pub fn pc_lot_size(&self) -> u64 {
+            read::<u64>(self.data, PC_LOT_SIZE)
+        }
+
+        pub fn target_mint(&self) -> Pubkey {
+            read::<Pubkey>(self.data, TARGET_MINT)
+        }
+
+        pub fn serum_limit(&self) -> u16 {
+            read::<u16>(self.data, SERUM_LIMIT)
+        }
+
+        pub fn instruction(&self) -> PoolInstruction {
+            PoolInstruction::CreateOrder {
+                pool_seed: *self.pool_seed(),
+                side: self.side(),
+                limit_price: self.limit_price(),
+                ratio_of_pool_assets_to_trade: self.ratio_of_pool_assets_to_trade(),
+                order_type: self.order_type(),
+                client_id: self.client_id(),
+                self_trade_behavior: self.self_trade_behavior(),
+                source_index: self.source_index(),
+                target_index: self.target_index(),
+                market_index: self.market_index(),
+                coin_lot_size: self.coin_lot_size(),
+                pc_lot_size: self.pc_lot_size(),
+                target_mint: self.target_mint(),
+                serum_limit: self.serum_limit(),
             }
-            6 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                let pool_token_amount = rest
-                    .get(32..40)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                Self::Redeem {
-                    pool_seed,
-                    pool_token_amount,
+        }
+    }
+
+    /// [PoolInstruction::Create](../enum.PoolInstruction.html#variant.Create) data in either layout
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct CreateView<'a> {
+        data: &'a [u8],
+        layout: CreateLayout,
+        markets: &'a [u8],
+        deposit_amounts: &'a [u8],
+    }
+
+    impl<'a> CreateView<'a> {
+        pub fn new(data: &'a [u8]) -> Result<Self, UnpackError> {
+            let (&tag, rest) = data.split_first().ok_or(UnpackError::EmptyInput)?;
+            let layout = match tag {
+                CREATE_TAG => CreateLayout::Legacy,
+                CREATE_V2_TAG => CreateLayout::V2,
+                _ => return Err(not_a(data)),
+            };
+            let reader = PayloadReader {
+                tag,
+                rest,
+                input_len: data.len(),
+            };
+            if data.len() < FEE_RATIO.end {
+                // Report the first fixed field that is cut
+                let head = PoolInstruction::layout(tag).ok_or(UnpackError::UnknownTag(tag))?;
+                for (field, range) in head.ranges() {
+                    if data.len() < range.end {
+                        return Err(reader.layout_error(LayoutError::Truncated { field, range }));
+                    }
                 }
             }
-            7 => {
-                let pool_seed: [u8; 32] = rest
-                    .get(..32)
-                    .and_then(|slice| slice.try_into().ok())
-                    .unwrap();
-                Self::CollectFees { pool_seed }
+            // The tail offsets are relative to the end of the tag
+            let markets_start = FEE_RATIO.end - 1;
+            let markets_end = markets_start + 32 * read::<u16>(data, NUMBER_OF_MARKETS) as usize;
+            if rest.len() < markets_end {
+                // The first market that is cut
+                let start = markets_start + (rest.len() - markets_start) / 32 * 32;
+                return Err(reader.truncated("markets", start..start + 32));
             }
-            _ => {
-                msg!("Unsupported tag");
-                return Err(InvalidInstruction.into());
+            let deposit_amounts = if tag == CREATE_V2_TAG {
+                let number_of_deposits = reader.u16("number_of_deposits", markets_end..markets_end + 2)?;
+                let start = markets_end + 2;
+                let end = start + 8 * number_of_deposits as usize;
+                let deposit_amounts = reader.bytes("deposit_amounts", start..end)?;
+                reader.expect_end(end)?;
+                deposit_amounts
+            } else {
+                let deposit_amounts = &rest[markets_end..];
+                let partial = deposit_amounts.len() % 8;
+                if partial != 0 {
+                    let start = markets_end + deposit_amounts.len() - partial;
+                    return Err(reader.truncated("deposit_amounts", start..start + 8));
+                }
+                deposit_amounts
+            };
+            Ok(Self {
+                data,
+                layout,
+                markets: &rest[markets_start..markets_end],
+                deposit_amounts,
+            })
+        }
+
+        pub fn layout(&self) -> CreateLayout {
+            self.layout
+        }
+
+        pub fn pool_seed(&self) -> &'a [u8; 32] {
+            pool_seed(self.data)
+        }
+
+        pub fn fee_collection_period(&self) -> u64 {
+            read::<u64>(self.data, FEE_COLLECTION_PERIOD)
+        }
+
+        pub fn fee_ratio(&self) -> u16 {
+            read::<u16>(self.data, FEE_RATIO)
+        }
+
+        pub fn number_of_markets(&self) -> u16 {
+            read::<u16>(self.data, NUMBER_OF_MARKETS)
+        }
+
+        pub fn markets(&self) -> impl ExactSizeIterator<Item = Pubkey> + 'a {
+            self.markets.chunks_exact(32).map(|bytes| read::<Pubkey>(bytes, 0..32))
+        }
+
+        pub fn number_of_deposits(&self) -> usize {
+            self.deposit_amounts.len() / 8
+        }
+
+        pub fn deposit_amounts(&self) -> impl ExactSizeIterator<Item = u64> + 'a {
+            self.deposit_amounts.chunks_exact(8).map(|bytes| read::<u64>(bytes, 0..8))
+        }
+
+        pub fn instruction(&self) -> PoolInstruction {
+            PoolInstruction::Create {
+                pool_seed: *self.pool_seed(),
+                fee_collection_period: self.fee_collection_period(),
+                fee_ratio: self.fee_ratio(),
+                deposit_amounts: self.deposit_amounts().collect(),
+                markets: self.markets().collect(),
             }
-        })
+        }
     }
 
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match self {
-            Self::Init {
-                pool_seed,
-                max_number_of_assets,
-                number_of_markets,
-            } => {
-                buf.push(0);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(&max_number_of_assets.to_le_bytes());
-                buf.extend_from_slice(&number_of_markets.to_le_bytes());
+    #[cfg(test)]
+    mod test {
+        use std::num::{NonZeroU16, NonZeroU64};
+
+        use serum_dex::{
+            instruction::SelfTradeBehavior,
+            matching::{OrderType, Side},
+        };
+        use solana_program::pubkey::Pubkey;
+
+        use super::*;
+
+        fn create_order() -> PoolInstruction {
+            PoolInstruction::CreateOrder {
+                pool_seed: [4; 32],
+                side: Side::Ask,
+                limit_price: NonZeroU64::new(23_457).unwrap(),
+                ratio_of_pool_assets_to_trade: NonZeroU16::new(39_321).unwrap(),
+                order_type: OrderType::ImmediateOrCancel,
+                client_id: 42,
+                self_trade_behavior: SelfTradeBehavior::CancelProvide,
+                source_index: 1,
+                target_index: 2,
+                market_index: 3,
+                coin_lot_size: 100_000_000,
+                pc_lot_size: 100,
+                target_mint: Pubkey::new_unique(),
+                serum_limit: 65,
             }
-            Self::Create {
-                pool_seed,
-                fee_collection_period,
-                fee_ratio,
-                deposit_amounts,
-                markets,
-            } => {
-                buf.push(1);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(&(markets.len() as u16).to_le_bytes());
-                buf.extend_from_slice(&fee_collection_period.to_le_bytes());
-                buf.extend_from_slice(&fee_ratio.to_le_bytes());
-                for market in markets {
-                    buf.extend_from_slice(&market.to_bytes())
-                }
-                for amount in deposit_amounts.iter() {
-                    buf.extend_from_slice(&amount.to_le_bytes());
-                }
+        }
+
+        #[test]
+        fn test_ranges() {
+            let layout = PoolInstruction::layout(CREATE_ORDER_TAG).unwrap();
+            let ranges = vec![
+                ("pool_seed", POOL_SEED),
+                ("side", SIDE),
+                ("limit_price", LIMIT_PRICE),
+                ("ratio_of_pool_assets_to_trade", RATIO_OF_POOL_ASSETS_TO_TRADE),
+                ("order_type", ORDER_TYPE),
+                ("client_id", CLIENT_ID),
+                ("self_trade_behavior", SELF_TRADE_BEHAVIOR),
+                ("source_index", SOURCE_INDEX),
+                ("target_index", TARGET_INDEX),
+                ("market_index", MARKET_INDEX),
+                ("coin_lot_size", COIN_LOT_SIZE),
+                ("pc_lot_size", PC_LOT_SIZE),
+                ("target_mint", TARGET_MINT),
+                ("serum_limit", SERUM_LIMIT),
+            ];
+            assert_eq!(layout.ranges(), ranges);
+            assert_eq!(layout.size(), CREATE_ORDER_LEN);
+            for tag in [CREATE_TAG, CREATE_V2_TAG].iter() {
+                let layout = PoolInstruction::layout(*tag).unwrap();
+                let ranges = vec![
+                    ("pool_seed", POOL_SEED),
+                    ("number_of_markets", NUMBER_OF_MARKETS),
+                    ("fee_collection_period", FEE_COLLECTION_PERIOD),
+                    ("fee_ratio", FEE_RATIO),
+                ];
+                assert_eq!(layout.ranges(), ranges);
             }
-            Self::Deposit {
-                pool_seed,
-                pool_token_amount,
-            } => {
-                buf.push(2);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+        }
+
+        #[test]
+        fn test_create_order_view() {
+            let instruction = create_order();
+            let data = instruction.pack();
+            let view = CreateOrderView::new(&data).unwrap();
+            assert_eq!(view.instruction(), instruction);
+            assert_eq!(view.limit_price().get(), 23_457);
+            assert_eq!(view.side(), Side::Ask);
+            assert_eq!(view.pool_seed(), &[4; 32]);
+
+            // Rejected data fails as try_unpack
+            for len in 0..data.len() {
+                assert_eq!(CreateOrderView::new(&data[..len]), Err(PoolInstruction::try_unpack(&data[..len]).unwrap_err()));
             }
-            Self::CreateOrder {
-                pool_seed,
-                side,
-                limit_price,
-                ratio_of_pool_assets_to_trade,
-                order_type,
-                client_id,
-                self_trade_behavior,
-                source_index,
-                target_index,
-                market_index,
-                coin_lot_size,
-                pc_lot_size,
-                target_mint,
-                serum_limit
-            } => {
-                buf.push(3);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(
-                    &match side {
-                        Side::Bid => 0u8,
-                        Side::Ask => 1,
-                    }
-                    .to_le_bytes(),
-                );
-                buf.extend_from_slice(&limit_price.get().to_le_bytes());
-                buf.extend_from_slice(&ratio_of_pool_assets_to_trade.get().to_le_bytes());
-                buf.extend_from_slice(
-                    &match order_type {
-                        OrderType::Limit => 0u8,
-                        OrderType::ImmediateOrCancel => 1,
-                        OrderType::PostOnly => 2,
-                    }
-                    .to_le_bytes(),
-                );
-                buf.extend_from_slice(&client_id.to_le_bytes());
-                buf.extend_from_slice(
-                    &match self_trade_behavior {
-                        SelfTradeBehavior::DecrementTake => 0u8,
-                        SelfTradeBehavior::CancelProvide => 1,
-                        SelfTradeBehavior::AbortTransaction => 2,
-                    }
-                    .to_le_bytes(),
-                );
-                buf.extend_from_slice(&source_index.to_le_bytes());
-                buf.extend_from_slice(&target_index.to_le_bytes());
-                buf.extend_from_slice(&market_index.to_le_bytes());
-                buf.extend_from_slice(&coin_lot_size.to_le_bytes());
-                buf.extend_from_slice(&pc_lot_size.to_le_bytes());
-                buf.extend_from_slice(&target_mint.to_bytes());
-                buf.extend_from_slice(&serum_limit.to_le_bytes())
+            for range in [SIDE, LIMIT_PRICE, ORDER_TYPE, SELF_TRADE_BEHAVIOR].iter() {
+                let mut invalid = data.clone();
+                for byte in invalid[range.clone()].iter_mut() {
+                    *byte = if range.len() == 1 { 7 } else { 0 };
+                }
+                let err = PoolInstruction::try_unpack(&invalid).unwrap_err();
+                assert!(matches!(err, UnpackError::InvalidValue { .. }));
+                assert_eq!(CreateOrderView::new(&invalid), Err(err));
             }
-            Self::CancelOrder {
-                pool_seed,
-                side,
-                order_id,
-            } => {
-                buf.push(4);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(
-                    &match side {
-                        Side::Bid => 0u8,
-                        Side::Ask => 1,
+            let deposit = PoolInstruction::Deposit {
+                pool_seed: [4; 32],
+                pool_token_amount: 1,
+            };
+            assert_eq!(CreateOrderView::new(&deposit.pack()), Err(UnpackError::UnknownTag(2)));
+        }
+
+        #[test]
+        fn test_create_view() {
+            let instruction = PoolInstruction::Create {
+                pool_seed: [5; 32],
+                fee_collection_period: 604_800,
+                fee_ratio: 150,
+                deposit_amounts: vec![1_000, 2_000, 3_000],
+                markets: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            };
+            for layout in [CreateLayout::Legacy, CreateLayout::V2].iter() {
+                let data = instruction.pack_with_layout(*layout).unwrap();
+                let view = CreateView::new(&data).unwrap();
+                assert_eq!(view.layout(), *layout);
+                assert_eq!(view.instruction(), instruction);
+                assert_eq!(view.number_of_markets(), 2);
+                assert_eq!(view.markets().len(), 2);
+                assert_eq!(view.number_of_deposits(), 3);
+                assert_eq!(view.deposit_amounts().sum::<u64>(), 6_000);
+                for len in 0..data.len() {
+                    match PoolInstruction::try_unpack(&data[..len]) {
+                        Ok(decoded) => assert_eq!(CreateView::new(&data[..len]).unwrap().instruction(), decoded),
+                        Err(err) => assert_eq!(CreateView::new(&data[..len]), Err(err)),
                     }
-                    .to_le_bytes(),
-                );
-                buf.extend_from_slice(&order_id.to_le_bytes());
-            }
-            Self::SettleFunds {
-                pool_seed,
-                pc_index,
-                coin_index,
-            } => {
-                buf.push(5);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(&pc_index.to_le_bytes());
-                buf.extend_from_slice(&coin_index.to_le_bytes());
-            }
-            Self::Redeem {
-                pool_seed,
-                pool_token_amount,
-            } => {
-                buf.push(6);
-                buf.extend_from_slice(pool_seed);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-            }
-            Self::CollectFees { pool_seed } => {
-                buf.push(7);
-                buf.extend_from_slice(pool_seed);
+                }
             }
-        };
-        buf
+            assert_eq!(CreateView::new(&create_order().pack()), Err(UnpackError::UnknownTag(3)));
+        }
     }
 }
 
@@ -843,6 +1492,305 @@ pub fn create_order(
     })
 }
 
+/// Conversions between human readable prices and sizes and the lot units of
+/// [PoolInstruction::CreateOrder](enum.PoolInstruction.html#variant.CreateOrder).
+/// Decimals are parsed as strings so no float rounding gets into the order.
+pub mod order_params {
+    use serum_dex::matching::Side;
+    use std::{
+        fmt,
+        num::{NonZeroU16, NonZeroU64},
+    };
+
+    /// `ratio_of_pool_assets_to_trade` trading the whole pool asset
+    pub const FULL_POOL_RATIO: u16 = u16::MAX;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum OrderParamError {
+        /// The string is not a non-negative decimal number such as `12` or `0.25`
+        InvalidDecimal,
+        /// One of the lot sizes is zero
+        ZeroLotSize,
+        /// The value is below one lot, or the order below one 65535th of the pool asset
+        RoundsToZero,
+        /// The order needs more than the pool asset holds
+        InsufficientPoolAssets,
+        Overflow,
+    }
+
+    impl fmt::Display for OrderParamError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                Self::InvalidDecimal => write!(f, "Not a decimal number"),
+                Self::ZeroLotSize => write!(f, "Lot sizes must be non zero"),
+                Self::RoundsToZero => write!(f, "Value rounds to zero lots"),
+                Self::InsufficientPoolAssets => write!(f, "Order is larger than the pool asset"),
+                Self::Overflow => write!(f, "Value overflows"),
+            }
+        }
+    }
+
+    impl std::error::Error for OrderParamError {}
+
+    /// Token decimals and lot sizes of a serum market
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct MarketLots {
+        pub coin_decimals: u8,
+        pub pc_decimals: u8,
+        pub coin_lot_size: u64,
+        pub pc_lot_size: u64,
+    }
+
+    /// `CreateOrder` fields computed by [MarketLots::order_params](struct.MarketLots.html#method.order_params)
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct OrderParams {
+        pub limit_price: NonZeroU64,
+        pub ratio_of_pool_assets_to_trade: NonZeroU16,
+        pub coin_lot_size: u64,
+        pub pc_lot_size: u64,
+        /// Native amount of the source pool asset the order asks for, the ratio
+        /// is rounded down so the program never locks more
+        pub source_amount: u64,
+    }
+
+    /// Mantissa and number of fraction digits of a decimal string
+    fn parse_decimal(value: &str) -> Result<(u128, u32), OrderParamError> {
+        let mut parts = value.splitn(2, '.');
+        let integer = parts.next().unwrap_or("");
+        let fraction = parts.next().unwrap_or("");
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(OrderParamError::InvalidDecimal);
+        }
+        let mut mantissa: u128 = 0;
+        for c in integer.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).ok_or(OrderParamError::InvalidDecimal)?;
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as u128))
+                .ok_or(OrderParamError::Overflow)?;
+        }
+        Ok((mantissa, fraction.len() as u32))
+    }
+
+    fn pow10(exponent: u32) -> Result<u128, OrderParamError> {
+        10u128.checked_pow(exponent).ok_or(OrderParamError::Overflow)
+    }
+
+    fn mul(a: u128, b: u128) -> Result<u128, OrderParamError> {
+        a.checked_mul(b).ok_or(OrderParamError::Overflow)
+    }
+
+    fn ceil_div(numerator: u128, denominator: u128) -> u128 {
+        let quotient = numerator / denominator;
+        if quotient * denominator < numerator {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+
+    fn to_non_zero(value: u128) -> Result<NonZeroU64, OrderParamError> {
+        if value > u64::MAX as u128 {
+            return Err(OrderParamError::Overflow);
+        }
+        NonZeroU64::new(value as u64).ok_or(OrderParamError::RoundsToZero)
+    }
+
+    /// `numerator / denominator` truncated to `digits` fraction digits, without trailing zeros
+    fn format_decimal(numerator: u128, denominator: u128, digits: u32) -> Result<String, OrderParamError> {
+        let mut result = (numerator / denominator).to_string();
+        let mut remainder = numerator % denominator;
+        let mut fraction = String::new();
+        for _ in 0..digits {
+            if remainder == 0 {
+                break;
+            }
+            remainder = mul(remainder, 10)?;
+            fraction.push(std::char::from_digit((remainder / denominator) as u32, 10).unwrap());
+            remainder %= denominator;
+        }
+        let fraction = fraction.trim_end_matches('0');
+        if !fraction.is_empty() {
+            result.push('.');
+            result.push_str(fraction);
+        }
+        Ok(result)
+    }
+
+    impl MarketLots {
+        fn check(&self) -> Result<(), OrderParamError> {
+            if self.coin_lot_size == 0 || self.pc_lot_size == 0 {
+                return Err(OrderParamError::ZeroLotSize);
+            }
+            Ok(())
+        }
+
+        /// Price of one coin in pc as `limit_price`, rounded down for bids and up
+        /// for asks so the order is never worse than `price`
+        pub fn limit_price(&self, price: &str, side: Side) -> Result<NonZeroU64, OrderParamError> {
+            self.check()?;
+            let (mantissa, scale) = parse_decimal(price)?;
+            let numerator = mul(
+                mul(mantissa, pow10(self.pc_decimals as u32)?)?,
+                self.coin_lot_size as u128,
+            )?;
+            let denominator = mul(
+                mul(pow10(scale)?, pow10(self.coin_decimals as u32)?)?,
+                self.pc_lot_size as u128,
+            )?;
+            match side {
+                Side::Bid => to_non_zero(numerator / denominator),
+                Side::Ask => to_non_zero(ceil_div(numerator, denominator)),
+            }
+        }
+
+        /// Size in coin as a number of coin lots, rounded down
+        pub fn coin_lots(&self, size: &str) -> Result<NonZeroU64, OrderParamError> {
+            self.check()?;
+            let (mantissa, scale) = parse_decimal(size)?;
+            let numerator = mul(mantissa, pow10(self.coin_decimals as u32)?)?;
+            let denominator = mul(pow10(scale)?, self.coin_lot_size as u128)?;
+            to_non_zero(numerator / denominator)
+        }
+
+        /// `limit_price` as a price of one coin in pc, truncated to
+        /// `coin_decimals + pc_decimals` fraction digits
+        pub fn price(&self, limit_price: NonZeroU64) -> Result<String, OrderParamError> {
+            self.check()?;
+            let numerator = mul(
+                mul(limit_price.get() as u128, self.pc_lot_size as u128)?,
+                pow10(self.coin_decimals as u32)?,
+            )?;
+            let denominator = mul(self.coin_lot_size as u128, pow10(self.pc_decimals as u32)?)?;
+            format_decimal(
+                numerator,
+                denominator,
+                self.coin_decimals as u32 + self.pc_decimals as u32,
+            )
+        }
+
+        /// A number of coin lots as a size in coin
+        pub fn size(&self, coin_lots: u64) -> Result<String, OrderParamError> {
+            self.check()?;
+            format_decimal(
+                mul(coin_lots as u128, self.coin_lot_size as u128)?,
+                pow10(self.coin_decimals as u32)?,
+                self.coin_decimals as u32,
+            )
+        }
+
+        /// Fields of an order of `size` coin at `price`. `source_pool_amount` is the
+        /// native amount the pool holds of the asset it pays with: pc for bids, coin for asks.
+        pub fn order_params(
+            &self,
+            side: Side,
+            price: &str,
+            size: &str,
+            source_pool_amount: u64,
+        ) -> Result<OrderParams, OrderParamError> {
+            let limit_price = self.limit_price(price, side)?;
+            let coin_lots = self.coin_lots(size)?.get() as u128;
+            let source_amount = match side {
+                Side::Bid => mul(mul(limit_price.get() as u128, coin_lots)?, self.pc_lot_size as u128)?,
+                Side::Ask => mul(coin_lots, self.coin_lot_size as u128)?,
+            };
+            let source_amount = to_non_zero(source_amount)?.get();
+            Ok(OrderParams {
+                limit_price,
+                ratio_of_pool_assets_to_trade: ratio_of_pool_assets_to_trade(source_amount, source_pool_amount)?,
+                coin_lot_size: self.coin_lot_size,
+                pc_lot_size: self.pc_lot_size,
+                source_amount,
+            })
+        }
+    }
+
+    /// Share of `pool_amount` that `amount` represents, rounded down
+    pub fn ratio_of_pool_assets_to_trade(amount: u64, pool_amount: u64) -> Result<NonZeroU16, OrderParamError> {
+        if amount > pool_amount {
+            return Err(OrderParamError::InsufficientPoolAssets);
+        }
+        if amount == 0 {
+            return Err(OrderParamError::RoundsToZero);
+        }
+        let ratio = amount as u128 * FULL_POOL_RATIO as u128 / pool_amount as u128;
+        NonZeroU16::new(ratio as u16).ok_or(OrderParamError::RoundsToZero)
+    }
+
+    /// Native amount of `pool_amount` that `ratio` stands for, rounded down
+    pub fn pool_assets_to_trade(ratio: NonZeroU16, pool_amount: u64) -> u64 {
+        (pool_amount as u128 * ratio.get() as u128 / FULL_POOL_RATIO as u128) as u64
+    }
+
+    #[cfg(test)]
+    mod test {
+        use std::num::NonZeroU64;
+
+        use serum_dex::matching::Side;
+
+        use super::{pool_assets_to_trade, ratio_of_pool_assets_to_trade, MarketLots, OrderParamError};
+
+        // SOL/USDC: 0.1 SOL lots, 0.001 USDC ticks
+        const MARKET: MarketLots = MarketLots {
+            coin_decimals: 9,
+            pc_decimals: 6,
+            coin_lot_size: 100_000_000,
+            pc_lot_size: 100,
+        };
+
+        #[test]
+        fn test_price_and_size() {
+            assert_eq!(MARKET.limit_price("23.4567", Side::Bid).unwrap().get(), 23_456);
+            assert_eq!(MARKET.limit_price("23.4567", Side::Ask).unwrap().get(), 23_457);
+            assert_eq!(MARKET.limit_price("23.456", Side::Ask).unwrap().get(), 23_456);
+            assert_eq!(MARKET.limit_price("23", Side::Bid).unwrap().get(), 23_000);
+            assert_eq!(MARKET.price(NonZeroU64::new(23_456).unwrap()).unwrap(), "23.456");
+            assert_eq!(MARKET.price(NonZeroU64::new(23_000).unwrap()).unwrap(), "23");
+
+            assert_eq!(MARKET.coin_lots("1.25").unwrap().get(), 12);
+            assert_eq!(MARKET.coin_lots(".5").unwrap().get(), 5);
+            assert_eq!(MARKET.size(12).unwrap(), "1.2");
+
+            let bid = MARKET.order_params(Side::Bid, "23.4567", "1.25", 100_000_000).unwrap();
+            // 23_456 ticks * 12 lots * 100
+            assert_eq!(bid.source_amount, 28_147_200);
+            assert_eq!(bid.ratio_of_pool_assets_to_trade.get(), 18_446);
+            assert_eq!(bid.coin_lot_size, 100_000_000);
+            assert_eq!(bid.pc_lot_size, 100);
+            let ask = MARKET.order_params(Side::Ask, "23.4567", "1.25", 2_000_000_000).unwrap();
+            assert_eq!(ask.source_amount, 1_200_000_000);
+            assert_eq!(ask.ratio_of_pool_assets_to_trade.get(), 39_321);
+            assert!(pool_assets_to_trade(ask.ratio_of_pool_assets_to_trade, 2_000_000_000) <= ask.source_amount);
+        }
+
+        #[test]
+        fn test_errors() {
+            for invalid in &["", ".", "1.2.3", "-1", "1e3", "abc", " 1"] {
+                assert_eq!(MARKET.coin_lots(invalid), Err(OrderParamError::InvalidDecimal));
+            }
+            assert_eq!(MARKET.coin_lots("0.09"), Err(OrderParamError::RoundsToZero));
+            assert_eq!(MARKET.limit_price("0.0009", Side::Bid), Err(OrderParamError::RoundsToZero));
+            assert_eq!(MARKET.limit_price("0.0009", Side::Ask).unwrap().get(), 1);
+            assert_eq!(
+                MARKET.limit_price("100000000000000000000", Side::Bid),
+                Err(OrderParamError::Overflow)
+            );
+            assert_eq!(
+                MARKET.coin_lots("1000000000000000000000000000000000000000"),
+                Err(OrderParamError::Overflow)
+            );
+            let no_lots = MarketLots { pc_lot_size: 0, ..MARKET };
+            assert_eq!(no_lots.limit_price("1", Side::Bid), Err(OrderParamError::ZeroLotSize));
+            assert_eq!(
+                MARKET.order_params(Side::Ask, "23", "3", 2_000_000_000),
+                Err(OrderParamError::InsufficientPoolAssets)
+            );
+            assert_eq!(ratio_of_pool_assets_to_trade(1, 1_000_000), Err(OrderParamError::RoundsToZero));
+            assert_eq!(ratio_of_pool_assets_to_trade(5, 5).unwrap().get(), u16::MAX);
+        }
+    }
+}
+
 // Creates a `CancelOrder` instruction
 pub fn cancel_order(
     bonfidabot_program_id: &Pubkey,
@@ -961,17 +1909,216 @@ pub fn collect_fees(
     })
 }
 
+/// Addresses derived by the program from a `pool_seed`. The pool account is the program
+/// address of `[pool_seed]`, the last seed byte being its bump, and the pool token mint the
+/// program address of `[pool_seed, [1]]`. Pool assets sit in associated token accounts of
+/// the pool account.
+pub mod pda {
+    use super::init;
+    use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+    use spl_associated_token_account::get_associated_token_address;
+    use std::convert::TryInto;
+
+    pub const POOL_MINT_SEED: &[u8] = &[1];
+
+    /// Derives the pool account, fails when the seed gives an address on the curve
+    pub fn pool_key(program_id: &Pubkey, pool_seed: &[u8; 32]) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::create_program_address(&[pool_seed], program_id)?)
+    }
+
+    /// Derives the pool token mint, fails when the seed gives an address on the curve
+    pub fn pool_mint_key(program_id: &Pubkey, pool_seed: &[u8; 32]) -> Result<Pubkey, ProgramError> {
+        Ok(Pubkey::create_program_address(&[pool_seed, POOL_MINT_SEED], program_id)?)
+    }
+
+    /// Completes 31 bytes into a pool seed ending with the highest valid bump
+    pub fn find_pool_seed(program_id: &Pubkey, seed_prefix: &[u8; 31]) -> ([u8; 32], Pubkey) {
+        let (pool_key, bump) = Pubkey::find_program_address(&[seed_prefix], program_id);
+        let mut pool_seed = [0u8; 32];
+        pool_seed[..31].copy_from_slice(seed_prefix);
+        pool_seed[31] = bump;
+        (pool_seed, pool_key)
+    }
+
+    /// Token account of the pool holding `mint`
+    pub fn pool_asset_address(pool_key: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address(pool_key, mint)
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct PoolAddresses {
+        pub pool_seed: [u8; 32],
+        pub pool_key: Pubkey,
+        pub mint_key: Pubkey,
+    }
+
+    impl PoolAddresses {
+        /// Addresses of an existing pool seed
+        pub fn new(program_id: &Pubkey, pool_seed: [u8; 32]) -> Result<Self, ProgramError> {
+            Ok(Self {
+                pool_seed,
+                pool_key: pool_key(program_id, &pool_seed)?,
+                mint_key: pool_mint_key(program_id, &pool_seed)?,
+            })
+        }
+
+        /// Finds the pool seed starting with `seed_prefix`. Returns `None` when the
+        /// mint of that seed is on the curve, another prefix has to be tried then.
+        pub fn find(program_id: &Pubkey, seed_prefix: &[u8; 31]) -> Option<Self> {
+            let (pool_seed, pool_key) = find_pool_seed(program_id, seed_prefix);
+            let mint_key = pool_mint_key(program_id, &pool_seed).ok()?;
+            Some(Self {
+                pool_seed,
+                pool_key,
+                mint_key,
+            })
+        }
+
+        pub fn bump(&self) -> u8 {
+            self.pool_seed[31]
+        }
+
+        pub fn seed_prefix(&self) -> [u8; 31] {
+            self.pool_seed[..31].try_into().unwrap()
+        }
+
+        pub fn pool_asset_address(&self, mint: &Pubkey) -> Pubkey {
+            pool_asset_address(&self.pool_key, mint)
+        }
+
+        /// Pool asset accounts in the order of `mints`, as taken by the builders
+        pub fn pool_asset_addresses(&self, mints: &[Pubkey]) -> Vec<Pubkey> {
+            mints.iter().map(|mint| self.pool_asset_address(mint)).collect()
+        }
+
+        /// Seeds signing for the pool account
+        pub fn pool_signer_seeds(&self) -> [&[u8]; 1] {
+            [&self.pool_seed]
+        }
+
+        /// Seeds signing for the pool token mint when it is created by `Init`
+        pub fn mint_signer_seeds(&self) -> [&[u8]; 2] {
+            [&self.pool_seed, POOL_MINT_SEED]
+        }
+
+        /// Creates the `Init` instruction of the pool
+        #[allow(clippy::too_many_arguments)]
+        pub fn init(
+            &self,
+            spl_token_program_id: &Pubkey,
+            system_program_id: &Pubkey,
+            rent_program_id: &Pubkey,
+            bonfidabot_program_id: &Pubkey,
+            payer_key: &Pubkey,
+            max_number_of_assets: u32,
+            number_of_markets: u16,
+        ) -> Result<Instruction, ProgramError> {
+            init(
+                spl_token_program_id,
+                system_program_id,
+                rent_program_id,
+                bonfidabot_program_id,
+                &self.mint_key,
+                payer_key,
+                &self.pool_key,
+                self.pool_seed,
+                max_number_of_assets,
+                number_of_markets,
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+        use spl_associated_token_account::get_associated_token_address;
+
+        use super::{find_pool_seed, pool_key, pool_mint_key, PoolAddresses};
+        use crate::instruction::PoolInstruction;
+
+        #[test]
+        fn test_find_pool_seed() {
+            let program_id = Pubkey::new_unique();
+            let (pool_seed, key) = find_pool_seed(&program_id, &[7; 31]);
+            assert_eq!(pool_seed[..31], [7; 31]);
+            assert_eq!(pool_key(&program_id, &pool_seed), Ok(key));
+            // Any other bump gives another address or none
+            for bump in (0..=255u8).filter(|bump| *bump != pool_seed[31]) {
+                let mut other = pool_seed;
+                other[31] = bump;
+                assert_ne!(pool_key(&program_id, &other).ok(), Some(key));
+            }
+        }
+
+        #[test]
+        fn test_pool_addresses() {
+            let program_id = Pubkey::new_unique();
+            let found: Vec<PoolAddresses> = (0..=255u8)
+                .filter_map(|i| PoolAddresses::find(&program_id, &[i; 31]))
+                .collect();
+            assert!(!found.is_empty());
+            for addresses in found.iter() {
+                assert_eq!(PoolAddresses::new(&program_id, addresses.pool_seed), Ok(*addresses));
+                assert_eq!(addresses.seed_prefix(), addresses.pool_seed[..31]);
+                assert_eq!(
+                    Pubkey::create_program_address(&addresses.pool_signer_seeds(), &program_id),
+                    Ok(addresses.pool_key)
+                );
+                assert_eq!(
+                    Pubkey::create_program_address(&addresses.mint_signer_seeds(), &program_id),
+                    Ok(addresses.mint_key)
+                );
+            }
+            // Prefixes whose mint is on the curve are skipped by find and rejected by new
+            for i in 0..=255u8 {
+                let (pool_seed, _) = find_pool_seed(&program_id, &[i; 31]);
+                if pool_mint_key(&program_id, &pool_seed).is_err() {
+                    assert_eq!(PoolAddresses::find(&program_id, &[i; 31]), None);
+                    assert_eq!(PoolAddresses::new(&program_id, pool_seed), Err(ProgramError::InvalidSeeds));
+                }
+            }
+
+            let addresses = found[0];
+            let mints = [Pubkey::new_unique(), Pubkey::new_unique()];
+            assert_eq!(
+                addresses.pool_asset_addresses(&mints),
+                vec![
+                    get_associated_token_address(&addresses.pool_key, &mints[0]),
+                    get_associated_token_address(&addresses.pool_key, &mints[1]),
+                ]
+            );
+            let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+            let init = addresses
+                .init(&keys[0], &keys[1], &keys[2], &program_id, &keys[3], 10, 2)
+                .unwrap();
+            assert_eq!(init.accounts[3].pubkey, addresses.pool_key);
+            assert_eq!(init.accounts[4].pubkey, addresses.mint_key);
+            assert_eq!(
+                PoolInstruction::unpack(&init.data).unwrap(),
+                PoolInstruction::Init {
+                    pool_seed: addresses.pool_seed,
+                    max_number_of_assets: 10,
+                    number_of_markets: 2,
+                }
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::num::{NonZeroU16, NonZeroU64};
 
+    use program_instruction::test_utils::check_vectors;
     use serum_dex::{
         instruction::SelfTradeBehavior,
         matching::{OrderType, Side},
     };
     use solana_program::pubkey::Pubkey;
 
-    use super::PoolInstruction;
+    use program_instruction::ProgramInstruction;
+
+    use super::{CreateLayout, PackError, PoolInstruction, UnpackError, CREATE_V2_TAG};
 
     #[test]
     fn test_instruction_packing() {
@@ -1063,4 +2210,249 @@ mod test {
         let unpacked_collect_fees = PoolInstruction::unpack(&packed_collect_fees).unwrap();
         assert_eq!(original_collect_fees, unpacked_collect_fees);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_instruction_unpack_errors() {
+        assert_eq!(PoolInstruction::try_unpack(&[]), Err(UnpackError::EmptyInput));
+        assert_eq!(PoolInstruction::try_unpack(&[9]), Err(UnpackError::UnknownTag(9)));
+        assert_eq!(
+            PoolInstruction::try_unpack(&[2; 10]),
+            Err(UnpackError::Truncated {
+                tag: 2,
+                field: "pool_seed",
+                range: 1..33,
+                input_len: 10
+            })
+        );
+
+        let mut packed_cancel_order = PoolInstruction::CancelOrder {
+            pool_seed: [50u8; 32],
+            side: Side::Bid,
+            order_id: 42,
+        }
+        .pack();
+        packed_cancel_order[33] = 2;
+        assert_eq!(
+            PoolInstruction::try_unpack(&packed_cancel_order),
+            Err(UnpackError::InvalidValue {
+                tag: 4,
+                field: "side",
+                range: 33..34,
+                input_len: 50
+            })
+        );
+
+        let packed_create_order = PoolInstruction::CreateOrder {
+            pool_seed: [50u8; 32],
+            side: Side::Bid,
+            limit_price: NonZeroU64::new(23).unwrap(),
+            ratio_of_pool_assets_to_trade: NonZeroU16::new(500).unwrap(),
+            order_type: OrderType::PostOnly,
+            client_id: 0xff44,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            source_index: 42,
+            target_index: 78,
+            market_index: 41,
+            coin_lot_size: 41,
+            pc_lot_size: 41,
+            target_mint: Pubkey::new_unique(),
+            serum_limit: 5000,
+        }
+        .pack();
+        // Every cut reports the first field it ends in
+        for tag in 0..8 {
+            let layout = PoolInstruction::layout(tag).unwrap();
+            for len in 1..layout.size() {
+                let mut input = packed_create_order[..len].to_vec();
+                input[0] = tag;
+                let (field, range) = layout.ranges().into_iter().find(|(_, range)| range.end > len).unwrap();
+                assert_eq!(
+                    PoolInstruction::try_unpack(&input),
+                    Err(UnpackError::Truncated {
+                        tag,
+                        field,
+                        range,
+                        input_len: len
+                    })
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_unpack() {
+        let deposit = PoolInstruction::Deposit {
+            pool_seed: [3u8; 32],
+            pool_token_amount: 5,
+        };
+        let mut packed = deposit.pack();
+        assert_eq!(PoolInstruction::try_unpack_strict(&packed), Ok(deposit.clone()));
+        packed.extend_from_slice(&[0; 40]);
+        assert_eq!(PoolInstruction::try_unpack(&packed), Ok(deposit));
+        assert_eq!(
+            PoolInstruction::try_unpack_strict(&packed),
+            Err(UnpackError::TrailingBytes {
+                tag: 2,
+                expected_len: 41,
+                trailing_len: 40,
+            })
+        );
+        assert_eq!(PoolInstruction::try_unpack_strict(&[]), Err(UnpackError::EmptyInput));
+    }
+
+    #[test]
+    fn test_create_layouts() {
+        let original_create = PoolInstruction::Create {
+            pool_seed: [50u8; 32],
+            deposit_amounts: vec![23, 43, u64::MAX],
+            markets: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            fee_collection_period: 10_000,
+            fee_ratio: 15,
+        };
+
+        let packed_legacy = original_create.pack_with_layout(CreateLayout::Legacy).unwrap();
+        assert_eq!(packed_legacy, original_create.pack());
+        assert_eq!(packed_legacy[0], 1);
+        assert_eq!(PoolInstruction::try_unpack(&packed_legacy), Ok(original_create.clone()));
+
+        let packed_v2 = original_create.pack_with_layout(CreateLayout::V2).unwrap();
+        assert_eq!(packed_v2[0], CREATE_V2_TAG);
+        assert_eq!(packed_v2.len(), packed_legacy.len() + 2);
+        assert_eq!(PoolInstruction::try_unpack(&packed_v2), Ok(original_create.clone()));
+        assert_eq!(original_create.discriminant(), 1);
+
+        // Counts past u16::MAX are rejected rather than truncated
+        let create = |deposits: usize, markets: usize| PoolInstruction::Create {
+            pool_seed: [50u8; 32],
+            deposit_amounts: vec![1; deposits],
+            markets: vec![Pubkey::default(); markets],
+            fee_collection_period: 10_000,
+            fee_ratio: 15,
+        };
+        let too_many_deposits = create(u16::MAX as usize + 1, 1);
+        assert!(too_many_deposits.pack_with_layout(CreateLayout::Legacy).is_ok());
+        assert_eq!(
+            too_many_deposits.pack_with_layout(CreateLayout::V2),
+            Err(PackError::TooManyEntries { field: "deposit_amounts", len: 65_536 })
+        );
+        let too_many_markets = create(1, u16::MAX as usize + 1);
+        assert_eq!(
+            too_many_markets.pack_with_layout(CreateLayout::Legacy),
+            Err(PackError::TooManyEntries { field: "markets", len: 65_536 })
+        );
+        assert!(ProgramInstruction::pack(&too_many_markets).is_err());
+
+        // A legacy payload cut inside a deposit amount is no longer silently shortened
+        assert_eq!(
+            PoolInstruction::try_unpack(&packed_legacy[..packed_legacy.len() - 3]),
+            Err(UnpackError::Truncated {
+                tag: 1,
+                field: "deposit_amounts",
+                range: 125..133,
+                input_len: 130
+            })
+        );
+        // The v2 count catches truncation on an amount boundary and trailing bytes
+        assert_eq!(
+            PoolInstruction::try_unpack(&packed_v2[..packed_v2.len() - 8]),
+            Err(UnpackError::Truncated {
+                tag: CREATE_V2_TAG,
+                field: "deposit_amounts",
+                range: 111..135,
+                input_len: 127
+            })
+        );
+        let mut padded_v2 = packed_v2.clone();
+        padded_v2.extend_from_slice(&[0; 8]);
+        assert_eq!(
+            PoolInstruction::try_unpack(&padded_v2),
+            Err(UnpackError::TrailingBytes {
+                tag: CREATE_V2_TAG,
+                expected_len: 135,
+                trailing_len: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_layouts() {
+        let create_order = PoolInstruction::layout(3).unwrap();
+        assert_eq!(create_order.name, "CreateOrder");
+        assert_eq!(create_order.size(), 122);
+        assert_eq!(create_order.range("side"), Some(33..34));
+        assert_eq!(create_order.range("limit_price"), Some(34..42));
+        assert_eq!(create_order.range("serum_limit"), Some(120..122));
+        let target_mint = Pubkey::new_unique();
+        assert_eq!(
+            create_order.memcmp::<Pubkey>("target_mint", &target_mint),
+            Some((88, target_mint.to_bytes().to_vec()))
+        );
+        assert_eq!(create_order.offset("markets"), None);
+
+        let create = PoolInstruction::layout(CREATE_V2_TAG).unwrap();
+        assert!(create.tail);
+        assert_eq!(create.size(), 45);
+        assert_eq!(PoolInstruction::layout(9), None);
+
+        let instructions = vec![
+            PoolInstruction::Init {
+                pool_seed: [1; 32],
+                max_number_of_assets: 2,
+                number_of_markets: 3,
+            },
+            PoolInstruction::CancelOrder {
+                pool_seed: [1; 32],
+                side: Side::Bid,
+                order_id: 4,
+            },
+            PoolInstruction::CollectFees { pool_seed: [1; 32] },
+        ];
+        for instruction in instructions {
+            let packed = instruction.pack();
+            assert_eq!(packed.len(), PoolInstruction::layout(packed[0]).unwrap().size());
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let cancel_order = PoolInstruction::CancelOrder {
+            pool_seed: [1; 32],
+            side: Side::Bid,
+            order_id: 42,
+        };
+        let json = serde_json::to_value(&cancel_order).unwrap();
+        assert_eq!(json["instruction"], "CancelOrder");
+        assert_eq!(json["args"]["side"], "Bid");
+        assert_eq!(json["args"]["order_id"], "42");
+        assert_eq!(serde_json::from_value::<PoolInstruction>(json).unwrap(), cancel_order);
+
+        let create_order = PoolInstruction::CreateOrder {
+            pool_seed: [2; 32],
+            side: Side::Ask,
+            limit_price: NonZeroU64::new(23).unwrap(),
+            ratio_of_pool_assets_to_trade: NonZeroU16::new(500).unwrap(),
+            order_type: OrderType::PostOnly,
+            client_id: 7,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            source_index: 0,
+            target_index: 1,
+            market_index: 0,
+            coin_lot_size: 10,
+            pc_lot_size: 100,
+            target_mint: Pubkey::new_unique(),
+            serum_limit: 5,
+        };
+        let json = serde_json::to_string(&create_order).unwrap();
+        assert_eq!(serde_json::from_str::<PoolInstruction>(&json).unwrap(), create_order);
+    }
+
+    /// Decodes and encodes every vector of bonfida-bot.rs_vectors.json, any wire format change fails
+    #[test]
+    fn test_vectors() {
+        let mut vectors = serde_json::Deserializer::from_str(include_str!("bonfida-bot.rs_vectors.json"));
+        check_vectors(&mut vectors, |instruction: &PoolInstruction, layout| match layout {
+            Some("V2") => instruction.pack_with_layout(CreateLayout::V2).unwrap(),
+            _ => instruction.pack_with_layout(CreateLayout::Legacy).unwrap(),
+        });
+    }
+}
@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use raydium_amm::instruction::AmmInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // Whatever decodes strictly packs back to the same bytes.
+    if let Ok(instruction) = AmmInstruction::unpack_strict(data) {
+        assert_eq!(instruction.pack().unwrap(), data);
+    }
+    // `unpack` ignores the bytes after the last field, so only that prefix has to match.
+    if let Ok(instruction) = AmmInstruction::unpack(data) {
+        let packed = instruction.pack().unwrap();
+        assert!(data.starts_with(&packed));
+    }
+});
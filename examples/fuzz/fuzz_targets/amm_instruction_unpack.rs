@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use raydium_amm::instruction::AmmInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // Arbitrary bytes must decode or fail with an error, never panic.
+    let _ = AmmInstruction::unpack(data);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use program_instruction::ProgramInstruction;
+use spl_token_metadata::instruction::MetadataInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // Borsh decoding rejects unread bytes, so whatever decodes packs back to the same data.
+    if let Ok(instruction) = MetadataInstruction::unpack(data) {
+        assert_eq!(instruction.pack().unwrap(), data);
+    }
+});
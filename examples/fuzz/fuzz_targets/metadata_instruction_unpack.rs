@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use program_instruction::ProgramInstruction;
+use spl_token_metadata::instruction::MetadataInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // Arbitrary bytes must decode or fail with an error, never panic.
+    if let Ok(instruction) = MetadataInstruction::unpack(data) {
+        let _ = instruction.variant_name();
+        let _ = instruction.expected_account_count();
+    }
+});
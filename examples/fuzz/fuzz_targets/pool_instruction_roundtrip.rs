@@ -0,0 +1,15 @@
+#![no_main]
+use bonfida_bot::instruction::{CreateLayout, PoolInstruction, CREATE_V2_TAG};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Whatever decodes strictly packs back to the same bytes, in the layout it was read with.
+    if let Ok(instruction) = PoolInstruction::try_unpack_strict(data) {
+        let layout = if data[0] == CREATE_V2_TAG {
+            CreateLayout::V2
+        } else {
+            CreateLayout::Legacy
+        };
+        assert_eq!(instruction.pack_with_layout(layout).unwrap(), data);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+use bonfida_bot::instruction::PoolInstruction;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Arbitrary bytes must decode or fail with an error, never panic.
+    if let Ok(instruction) = PoolInstruction::try_unpack(data) {
+        assert_eq!(PoolInstruction::try_unpack(&instruction.pack()), Ok(instruction));
+    }
+});
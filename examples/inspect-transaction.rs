@@ -0,0 +1,642 @@
+//! Decodes the instructions of a transaction aimed at the bundled programs
+//!
+//! Usage: inspect-transaction [--json] [--program <name>=<program id>]... <transaction | file | ->
+//!
+//! The transaction is either a serialized transaction in base58 or base64, or
+//! the JSON of a `getTransaction` response, of its `transaction` or of its
+//! `message`. Inner instructions of the response are decoded too.
+
+use abi_codec::{account_labels, AbiCodec};
+use bonfida_bot::instruction::PoolInstruction;
+use metaplex_token_metadata::instruction::MetadataInstruction;
+use program_instruction::{AccountCount, ProgramInstruction};
+use raydium::instruction::AmmInstruction;
+use serde_json::{json, Map, Value};
+use std::{
+    env, fs,
+    io::{self, Read},
+    process,
+};
+
+type ExpectedAccounts = fn(&[u8]) -> Option<AccountCount>;
+
+/// Name, default program id, ABI and account count check of the bundled programs.
+/// The bonfida-bot program is deployed per operator, its id is given with `--program`.
+const PROGRAMS: &[(&str, Option<&str>, &str, ExpectedAccounts)] = &[
+    (
+        "raydium",
+        Some("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"),
+        include_str!("raydium.rs_ABI.json"),
+        expected_accounts::<AmmInstruction>,
+    ),
+    (
+        "bonfida-bot",
+        None,
+        include_str!("bonfida-bot.rs_ABI.json"),
+        expected_accounts::<PoolInstruction>,
+    ),
+    (
+        "metaplex-token-metadata",
+        Some("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"),
+        include_str!("metaplex-token-metadata.rs_ABI.json"),
+        expected_accounts::<MetadataInstruction>,
+    ),
+];
+
+/// Number of accounts the program expects for the instruction data, `None` when it doesn't unpack
+fn expected_accounts<T: ProgramInstruction>(data: &[u8]) -> Option<AccountCount> {
+    T::unpack(data).ok().map(|instruction| instruction.expected_account_count())
+}
+
+fn describe_count(count: &AccountCount) -> String {
+    match count.max {
+        Some(max) if max == count.min => max.to_string(),
+        Some(max) => format!("{} to {}", count.min, max),
+        None => format!("at least {}", count.min),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AccountKey {
+    pubkey: String,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct RawInstruction {
+    /// `#2` for a top level instruction, `#2.1` for an inner one
+    position: String,
+    program_id: String,
+    accounts: Vec<AccountKey>,
+    data: Vec<u8>,
+}
+
+struct Program {
+    name: &'static str,
+    program_id: String,
+    codec: AbiCodec,
+    expected_accounts: ExpectedAccounts,
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .input
+            .get(self.offset..self.offset + len)
+            .ok_or_else(|| format!("transaction truncated at offset {}", self.offset))?;
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Compact-u16 length prefix
+    fn short_vec_len(&mut self) -> Result<usize, String> {
+        let mut len = 0usize;
+        for shift in [0, 7, 14].iter() {
+            let byte = self.u8()?;
+            len |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+        }
+        Err(format!("invalid length prefix at offset {}", self.offset))
+    }
+
+    fn pubkey(&mut self) -> Result<String, String> {
+        Ok(bs58::encode(self.bytes(32)?).into_string())
+    }
+}
+
+/// Signer and writable flags of the static account keys of a message
+fn account_keys(keys: Vec<String>, header: [usize; 3]) -> Vec<AccountKey> {
+    let [num_required_signatures, num_readonly_signed, num_readonly_unsigned] = header;
+    let len = keys.len();
+    keys.into_iter()
+        .enumerate()
+        .map(|(index, pubkey)| AccountKey {
+            pubkey,
+            is_signer: index < num_required_signatures,
+            is_writable: if index < num_required_signatures {
+                index < num_required_signatures.saturating_sub(num_readonly_signed)
+            } else {
+                index < len.saturating_sub(num_readonly_unsigned)
+            },
+        })
+        .collect()
+}
+
+fn resolve_accounts(keys: &[AccountKey], indexes: &[usize]) -> Result<Vec<AccountKey>, String> {
+    indexes
+        .iter()
+        .map(|index| {
+            keys.get(*index)
+                .cloned()
+                .ok_or_else(|| format!("account index {} out of {} keys", index, keys.len()))
+        })
+        .collect()
+}
+
+/// Parses a serialized legacy or v0 transaction
+fn parse_wire_transaction(input: &[u8]) -> Result<Vec<RawInstruction>, String> {
+    let mut reader = Reader { input, offset: 0 };
+    let signatures = reader.short_vec_len()?;
+    reader.bytes(64 * signatures)?;
+    let first = reader.u8()?;
+    let (versioned, num_required_signatures) = if first & 0x80 != 0 {
+        if first & 0x7f != 0 {
+            return Err(format!("unsupported message version {}", first & 0x7f));
+        }
+        (true, reader.u8()?)
+    } else {
+        (false, first)
+    };
+    let header = [num_required_signatures as usize, reader.u8()? as usize, reader.u8()? as usize];
+    let key_count = reader.short_vec_len()?;
+    let keys = (0..key_count).map(|_| reader.pubkey()).collect::<Result<Vec<_>, _>>()?;
+    let mut keys = account_keys(keys, header);
+    reader.bytes(32)?;
+    let instruction_count = reader.short_vec_len()?;
+    let mut compiled = Vec::with_capacity(instruction_count);
+    for _ in 0..instruction_count {
+        let program_id_index = reader.u8()? as usize;
+        let account_count = reader.short_vec_len()?;
+        let accounts = reader.bytes(account_count)?.iter().map(|index| *index as usize).collect::<Vec<_>>();
+        let data_len = reader.short_vec_len()?;
+        compiled.push((program_id_index, accounts, reader.bytes(data_len)?.to_vec()));
+    }
+    if versioned {
+        // Loaded addresses are only known on chain, they are named after their table
+        let mut writable = vec![];
+        let mut readonly = vec![];
+        for _ in 0..reader.short_vec_len()? {
+            let table = reader.pubkey()?;
+            for (addresses, is_writable) in [(&mut writable, true), (&mut readonly, false)].iter_mut() {
+                let count = reader.short_vec_len()?;
+                for index in reader.bytes(count)? {
+                    addresses.push(AccountKey {
+                        pubkey: format!("{}#{}", table, index),
+                        is_signer: false,
+                        is_writable: *is_writable,
+                    });
+                }
+            }
+        }
+        keys.extend(writable);
+        keys.extend(readonly);
+    }
+    if reader.offset != input.len() {
+        return Err(format!("{} trailing bytes", input.len() - reader.offset));
+    }
+    compiled
+        .into_iter()
+        .enumerate()
+        .map(|(position, (program_id_index, accounts, data))| {
+            Ok(RawInstruction {
+                position: format!("#{}", position),
+                program_id: resolve_accounts(&keys, &[program_id_index])?.remove(0).pubkey,
+                accounts: resolve_accounts(&keys, &accounts)?,
+                data,
+            })
+        })
+        .collect()
+}
+
+fn parse_json_instruction(keys: &[AccountKey], instruction: &Value, position: String) -> Result<RawInstruction, String> {
+    let program_id = match (instruction.get("programIdIndex"), instruction.get("programId")) {
+        (Some(index), _) => {
+            let index = index.as_u64().ok_or("invalid programIdIndex")? as usize;
+            resolve_accounts(keys, &[index])?.remove(0).pubkey
+        }
+        (None, Some(Value::String(program_id))) => program_id.clone(),
+        _ => return Err(format!("{}: missing programIdIndex", position)),
+    };
+    let accounts = instruction
+        .get("accounts")
+        .and_then(Value::as_array)
+        .ok_or_else(|| format!("{}: missing accounts", position))?
+        .iter()
+        .map(|account| match account {
+            Value::Number(index) => Ok(resolve_accounts(keys, &[index.as_u64().unwrap_or(u64::MAX) as usize])?.remove(0)),
+            Value::String(pubkey) => Ok(keys.iter().find(|key| &key.pubkey == pubkey).cloned().unwrap_or(AccountKey {
+                pubkey: pubkey.clone(),
+                is_signer: false,
+                is_writable: false,
+            })),
+            _ => Err(format!("{}: invalid account {}", position, account)),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let data = match instruction.get("data") {
+        Some(Value::String(data)) => bs58::decode(data)
+            .into_vec()
+            .map_err(|_| format!("{}: data is not base58", position))?,
+        // Instructions parsed by the RPC node are not ours to decode
+        _ => vec![],
+    };
+    Ok(RawInstruction {
+        position,
+        program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Parses a `getTransaction` response, a transaction or a message in JSON
+fn parse_json_transaction(value: &Value) -> Result<Vec<RawInstruction>, String> {
+    let value = value.get("result").unwrap_or(value);
+    let meta = value.get("meta");
+    let transaction = value.get("transaction").unwrap_or(value);
+    // `[data, encoding]` of the binary encodings
+    if let Some(Value::String(data)) = transaction.get(0) {
+        let bytes = match transaction.get(1).and_then(Value::as_str) {
+            Some("base64") => base64::decode(data).map_err(|error| error.to_string())?,
+            _ => bs58::decode(data).into_vec().map_err(|error| error.to_string())?,
+        };
+        return parse_wire_transaction(&bytes);
+    }
+    let message = transaction.get("message").unwrap_or(transaction);
+    let header = message.get("header");
+    let header_value = |name: &str| {
+        header
+            .and_then(|header| header.get(name))
+            .and_then(Value::as_u64)
+            .unwrap_or(0) as usize
+    };
+    let account_keys_json = message
+        .get("accountKeys")
+        .and_then(Value::as_array)
+        .ok_or("missing accountKeys")?;
+    let mut keys = vec![];
+    let mut static_keys = vec![];
+    for key in account_keys_json {
+        match key {
+            Value::String(pubkey) => static_keys.push(pubkey.clone()),
+            // jsonParsed encoding
+            key => keys.push(AccountKey {
+                pubkey: key.get("pubkey").and_then(Value::as_str).ok_or("invalid account key")?.to_string(),
+                is_signer: key.get("signer").and_then(Value::as_bool).unwrap_or(false),
+                is_writable: key.get("writable").and_then(Value::as_bool).unwrap_or(false),
+            }),
+        }
+    }
+    keys.extend(account_keys(
+        static_keys,
+        [
+            header_value("numRequiredSignatures"),
+            header_value("numReadonlySignedAccounts"),
+            header_value("numReadonlyUnsignedAccounts"),
+        ],
+    ));
+    if let Some(loaded) = meta.and_then(|meta| meta.get("loadedAddresses")) {
+        for (name, is_writable) in [("writable", true), ("readonly", false)].iter() {
+            for pubkey in loaded.get(name).and_then(Value::as_array).into_iter().flatten() {
+                keys.push(AccountKey {
+                    pubkey: pubkey.as_str().ok_or("invalid loaded address")?.to_string(),
+                    is_signer: false,
+                    is_writable: *is_writable,
+                });
+            }
+        }
+    }
+    let mut instructions = vec![];
+    for (position, instruction) in message
+        .get("instructions")
+        .and_then(Value::as_array)
+        .ok_or("missing instructions")?
+        .iter()
+        .enumerate()
+    {
+        instructions.push(parse_json_instruction(&keys, instruction, format!("#{}", position))?);
+    }
+    let inner_instructions = meta
+        .and_then(|meta| meta.get("innerInstructions"))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten();
+    for inner in inner_instructions {
+        let index = inner.get("index").and_then(Value::as_u64).unwrap_or(0);
+        for (position, instruction) in inner
+            .get("instructions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .enumerate()
+        {
+            instructions.push(parse_json_instruction(&keys, instruction, format!("#{}.{}", index, position))?);
+        }
+    }
+    Ok(instructions)
+}
+
+fn parse_transaction(input: &str) -> Result<Vec<RawInstruction>, String> {
+    let input = input.trim();
+    if input.starts_with('{') || input.starts_with('[') {
+        let value: Value = serde_json::from_str(input).map_err(|error| error.to_string())?;
+        return parse_json_transaction(&value);
+    }
+    // A base64 string may only use base58 characters, the one that parses wins
+    let mut error = "neither base58 nor base64".to_string();
+    let decoded = [bs58::decode(input).into_vec().ok(), base64::decode(input).ok()];
+    for bytes in decoded.iter().flatten() {
+        match parse_wire_transaction(bytes) {
+            Ok(instructions) => return Ok(instructions),
+            Err(parse_error) => error = parse_error,
+        }
+    }
+    Err(error)
+}
+
+fn inspect(programs: &[Program], instruction: &RawInstruction) -> Value {
+    let program = match programs.iter().find(|program| program.program_id == instruction.program_id) {
+        Some(program) => program,
+        None => {
+            return json!({
+                "position": instruction.position,
+                "programId": instruction.program_id,
+            })
+        }
+    };
+    let mut inspected = Map::new();
+    inspected.insert("position".to_string(), json!(instruction.position));
+    inspected.insert("programId".to_string(), json!(instruction.program_id));
+    inspected.insert("program".to_string(), json!(program.name));
+    let count = instruction.accounts.len();
+    let unknown_labels = || (0..count).map(|index| format!("unknown[{}]", index)).collect();
+    let labels = match program.codec.decode_data(&instruction.data) {
+        Ok(decoded) => {
+            inspected.insert("name".to_string(), json!(decoded.instruction.name));
+            let mut warnings = vec![];
+            if decoded.trailing_len != 0 {
+                warnings.push(format!(
+                    "{} trailing bytes after the {} bytes of the instruction, ignored by the program",
+                    decoded.trailing_len,
+                    instruction.data.len() - decoded.trailing_len
+                ));
+            }
+            // Accounts are only named when their count tells which of them were passed
+            let labels = match (program.expected_accounts)(&instruction.data) {
+                Some(expected) if !expected.contains(count) => {
+                    warnings.push(format!("expected {} accounts, got {}", describe_count(&expected), count));
+                    unknown_labels()
+                }
+                _ => account_labels(decoded.instruction, count),
+            };
+            inspected.insert("fields".to_string(), Value::Object(decoded.fields));
+            if !warnings.is_empty() {
+                inspected.insert("warnings".to_string(), json!(warnings));
+            }
+            labels
+        }
+        Err(error) => {
+            inspected.insert("error".to_string(), json!(error.to_string()));
+            inspected.insert("data".to_string(), json!(bs58::encode(&instruction.data).into_string()));
+            unknown_labels()
+        }
+    };
+    let accounts = instruction
+        .accounts
+        .iter()
+        .zip(labels)
+        .map(|(account, label)| {
+            json!({
+                "name": label,
+                "pubkey": account.pubkey,
+                "isSigner": account.is_signer,
+                "isWritable": account.is_writable,
+            })
+        })
+        .collect::<Vec<_>>();
+    inspected.insert("accounts".to_string(), Value::Array(accounts));
+    Value::Object(inspected)
+}
+
+fn print_inspected(inspected: &Value) {
+    let field = |name: &str| inspected.get(name).and_then(Value::as_str).unwrap_or_default();
+    if inspected.get("program").is_none() {
+        println!("{} {} (unknown program)", field("position"), field("programId"));
+        return;
+    }
+    match inspected.get("error") {
+        Some(error) => println!(
+            "{} {} error: {}, data {}",
+            field("position"),
+            field("program"),
+            error.as_str().unwrap_or_default(),
+            field("data")
+        ),
+        None => println!("{} {} {}", field("position"), field("program"), field("name")),
+    }
+    for warning in inspected.get("warnings").and_then(Value::as_array).into_iter().flatten() {
+        println!("    warning: {}", warning.as_str().unwrap_or_default());
+    }
+    for (name, value) in inspected.get("fields").and_then(Value::as_object).into_iter().flatten() {
+        println!("    {}: {}", name, value);
+    }
+    for account in inspected.get("accounts").and_then(Value::as_array).into_iter().flatten() {
+        let flag = |name: &str, letter: &'static str| match account.get(name).and_then(Value::as_bool) {
+            Some(true) => letter,
+            _ => "-",
+        };
+        println!(
+            "    {}{} {:<44} {}",
+            flag("isWritable", "w"),
+            flag("isSigner", "s"),
+            account.get("pubkey").and_then(Value::as_str).unwrap_or_default(),
+            account.get("name").and_then(Value::as_str).unwrap_or_default()
+        );
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: inspect-transaction [--json] [--program <name>=<program id>]... <transaction | file | ->");
+    process::exit(2);
+}
+
+fn main() {
+    let mut program_ids = PROGRAMS
+        .iter()
+        .map(|(name, program_id, _, _)| (*name, program_id.map(str::to_string)))
+        .collect::<Vec<_>>();
+    let mut as_json = false;
+    let mut input = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => as_json = true,
+            "--program" => {
+                let value = args.next().unwrap_or_else(|| usage());
+                let mut parts = value.splitn(2, '=');
+                let (name, program_id) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_else(|| usage()));
+                match program_ids.iter_mut().find(|(known, _)| *known == name) {
+                    Some((_, known_id)) => *known_id = Some(program_id.to_string()),
+                    None => {
+                        eprintln!("Unknown program {}", name);
+                        process::exit(2);
+                    }
+                }
+            }
+            "-h" | "--help" => usage(),
+            _ if input.is_none() => input = Some(arg),
+            _ => usage(),
+        }
+    }
+    let input = match input.as_deref() {
+        None => usage(),
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).expect("Failed to read stdin");
+            input
+        }
+        Some(input) => fs::read_to_string(input).unwrap_or_else(|_| input.to_string()),
+    };
+    let programs = PROGRAMS
+        .iter()
+        .zip(program_ids)
+        .filter_map(|((name, _, abi, expected_accounts), (_, program_id))| {
+            Some(Program {
+                name,
+                program_id: program_id?,
+                codec: AbiCodec::from_json(abi).expect("Invalid bundled ABI"),
+                expected_accounts: *expected_accounts,
+            })
+        })
+        .collect::<Vec<_>>();
+    let instructions = match parse_transaction(&input) {
+        Ok(instructions) => instructions,
+        Err(error) => {
+            eprintln!("Invalid transaction: {}", error);
+            process::exit(1);
+        }
+    };
+    let inspected = instructions
+        .iter()
+        .map(|instruction| inspect(&programs, instruction))
+        .collect::<Vec<_>>();
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&inspected).unwrap());
+    } else {
+        for inspected in inspected.iter() {
+            print_inspected(inspected);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{inspect, parse_transaction, AbiCodec, Program, PROGRAMS};
+
+    fn raydium() -> Program {
+        Program {
+            name: "raydium",
+            program_id: PROGRAMS[0].1.unwrap().to_string(),
+            codec: AbiCodec::from_json(PROGRAMS[0].2).unwrap(),
+            expected_accounts: PROGRAMS[0].3,
+        }
+    }
+
+    /// One signature, the payer, 18 writable accounts and the readonly program, then
+    /// one raydium instruction with `accounts` indexes into these keys
+    fn wire_transaction(accounts: &[u8], data: &[u8]) -> Vec<u8> {
+        let program = bs58::decode(PROGRAMS[0].1.unwrap()).into_vec().unwrap();
+        let mut transaction = vec![1];
+        transaction.extend_from_slice(&[0; 64]);
+        transaction.extend_from_slice(&[1, 0, 1, 20]);
+        for key in 1..=19u8 {
+            transaction.extend_from_slice(&[key; 32]);
+        }
+        transaction.extend_from_slice(&program);
+        transaction.extend_from_slice(&[9; 32]);
+        transaction.extend_from_slice(&[1, 19, accounts.len() as u8]);
+        transaction.extend_from_slice(accounts);
+        transaction.push(data.len() as u8);
+        transaction.extend_from_slice(data);
+        transaction
+    }
+
+    #[test]
+    fn test_wire_transaction() {
+        // A Withdraw of 100 with its 19 accounts, the payer signs as the user_owner
+        let accounts = (1..19).chain(0..1).collect::<Vec<u8>>();
+        let transaction = wire_transaction(&accounts, &[4, 100, 0, 0, 0, 0, 0, 0, 0]);
+
+        for encoded in [bs58::encode(&transaction).into_string(), base64::encode(&transaction)].iter() {
+            let instructions = parse_transaction(encoded).unwrap();
+            assert_eq!(instructions.len(), 1);
+            assert_eq!(instructions[0].accounts.len(), 19);
+            assert_eq!(instructions[0].accounts[0].pubkey, bs58::encode([2u8; 32]).into_string());
+            assert!(instructions[0].accounts[0].is_writable);
+            assert!(instructions[0].accounts[18].is_signer);
+            let inspected = inspect(&[raydium()], &instructions[0]);
+            assert_eq!(inspected["name"], "Withdraw");
+            assert_eq!(inspected["fields"]["amount"], 100);
+            assert!(inspected.get("warnings").is_none());
+            assert_eq!(inspected["accounts"][0]["name"], "spl_token_program");
+            assert_eq!(inspected["accounts"][1]["name"], "amm_id");
+            assert_eq!(inspected["accounts"][18]["name"], "user_owner");
+        }
+        assert!(parse_transaction(&bs58::encode(&transaction[..100]).into_string()).is_err());
+    }
+
+    #[test]
+    fn test_warnings() {
+        // Too few accounts for a Withdraw: flagged, and the accounts are not named
+        let transaction = wire_transaction(&[1, 0], &[4, 100, 0, 0, 0, 0, 0, 0, 0]);
+        let instructions = parse_transaction(&bs58::encode(&transaction).into_string()).unwrap();
+        let inspected = inspect(&[raydium()], &instructions[0]);
+        assert_eq!(inspected["name"], "Withdraw");
+        assert_eq!(inspected["warnings"], json!(["expected 19 accounts, got 2"]));
+        assert_eq!(inspected["accounts"][0]["name"], "unknown[0]");
+        assert_eq!(inspected["accounts"][1]["name"], "unknown[1]");
+
+        // Trailing bytes are ignored by the program, the fields still decode
+        let accounts = (1..19).chain(0..1).collect::<Vec<u8>>();
+        let transaction = wire_transaction(&accounts, &[4, 100, 0, 0, 0, 0, 0, 0, 0, 0xaa, 0xbb]);
+        let instructions = parse_transaction(&bs58::encode(&transaction).into_string()).unwrap();
+        let inspected = inspect(&[raydium()], &instructions[0]);
+        assert_eq!(inspected["fields"]["amount"], 100);
+        assert_eq!(
+            inspected["warnings"],
+            json!(["2 trailing bytes after the 9 bytes of the instruction, ignored by the program"])
+        );
+        assert_eq!(inspected["accounts"][18]["name"], "user_owner");
+    }
+
+    #[test]
+    fn test_json_transaction() {
+        let program_id = PROGRAMS[0].1.unwrap();
+        let response = json!({
+            "result": {
+                "meta": {
+                    "innerInstructions": [{
+                        "index": 0,
+                        "instructions": [{"programIdIndex": 1, "accounts": [0], "data": bs58::encode([7]).into_string()}],
+                    }],
+                },
+                "transaction": {
+                    "message": {
+                        "header": {"numRequiredSignatures": 1, "numReadonlySignedAccounts": 0, "numReadonlyUnsignedAccounts": 1},
+                        "accountKeys": [bs58::encode([1u8; 32]).into_string(), program_id],
+                        "instructions": [{"programIdIndex": 1, "accounts": [0], "data": bs58::encode([42]).into_string()}],
+                    },
+                },
+            },
+        });
+        let instructions = parse_transaction(&response.to_string()).unwrap();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].position, "#0.0");
+        assert_eq!(inspect(&[raydium()], &instructions[1])["name"], "WithdrawPnl");
+        let unknown = inspect(&[raydium()], &instructions[0]);
+        assert_eq!(unknown["error"], "unknown instruction code 42");
+        assert_eq!(unknown["accounts"][0]["name"], "unknown[0]");
+    }
+}
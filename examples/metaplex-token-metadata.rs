@@ -1,20 +1,30 @@
 use {
-  crate::{
-      deprecated_instruction::{MintPrintingTokensViaTokenArgs, SetReservationListArgs},
-      state::{Creator, Data, EDITION, EDITION_MARKER_BIT_SIZE, PREFIX},
+  crate::state::{
+      Creator, Data, MAX_CREATOR_LIMIT, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
   },
   borsh::{BorshDeserialize, BorshSerialize},
+  program_instruction::{
+      AccountAbi, AccountCount, FieldAbi, InstructionAbi, ProgramAbi, ProgramInstruction, TypeAbi,
+  },
   solana_program::{
       instruction::{AccountMeta, Instruction},
+      program_error::ProgramError,
       pubkey::Pubkey,
       sysvar,
   },
 };
 
+#[cfg(feature = "deprecated")]
+use crate::deprecated_instruction::{MintPrintingTokensViaTokenArgs, SetReservationListArgs};
+#[cfg(any(test, feature = "serde"))]
+use serde::{Deserialize, Serialize};
+
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 /// Args for update call
 pub struct UpdateMetadataAccountArgs {
+  #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_state::option_data"))]
   pub data: Option<Data>,
   pub update_authority: Option<Pubkey>,
   pub primary_sale_happened: Option<bool>,
@@ -22,9 +32,11 @@ pub struct UpdateMetadataAccountArgs {
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 /// Args for create call
 pub struct CreateMetadataAccountArgs {
   /// Note that unique metadatas are disabled for now.
+  #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_state::DataDef"))]
   pub data: Data,
   /// Whether you want your metadata to be updateable in the future.
   pub is_mutable: bool,
@@ -32,6 +44,7 @@ pub struct CreateMetadataAccountArgs {
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct CreateMasterEditionArgs {
   /// If set, means that no more than this number of editions can ever be minted. This is immutable.
   pub max_supply: Option<u64>,
@@ -39,12 +52,21 @@ pub struct CreateMasterEditionArgs {
 
 #[repr(C)]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug, Clone)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct MintNewEditionFromMasterEditionViaTokenArgs {
   pub edition: u64,
 }
 
 /// Instructions supported by the Metadata program.
-#[derive(BorshSerialize, BorshDeserialize, Clone)]
+///
+/// The `Deprecated*` variants are behind the `deprecated` feature, without it
+/// their tags decode to `Deprecated { tag, data }` so the other tags stay the same.
+///
+/// With the `serde` feature it is represented as `{"instruction": "SignMetadata", "args": {...}}`,
+/// `args` being left out for the variants without arguments.
+#[cfg_attr(feature = "deprecated", derive(BorshSerialize, BorshDeserialize))]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize), serde(tag = "instruction", content = "args"))]
 pub enum MetadataInstruction {
   /// Create Metadata object.
   ///   0. `[writable]`  Metadata key (pda of ['metadata', program id, mint id])
@@ -79,6 +101,7 @@ pub enum MetadataInstruction {
   ///   10. `[]` System program
   ///   11. `[]` Rent info
   ///   13. `[signer]`   One time authorization printing mint authority - must be provided if using max supply. THIS WILL TRANSFER AUTHORITY AWAY FROM THIS KEY.
+  #[cfg(feature = "deprecated")]
   DeprecatedCreateMasterEdition(CreateMasterEditionArgs),
 
   /// Given an authority token minted by the Printing mint of a master edition, and a brand new non-metadata-ed mint with one token
@@ -100,6 +123,7 @@ pub enum MetadataInstruction {
   ///   14. `[]` Rent info
   ///   15. `[optional/writable]` Reservation List - If present, and you are on this list, you can get
   ///        an edition number given by your position on the list.
+  #[cfg(feature = "deprecated")]
   DeprecatedMintNewEditionFromMasterEditionViaPrintingToken,
 
   /// Allows updating the primary sale boolean on Metadata solely through owning an account
@@ -128,6 +152,8 @@ pub enum MetadataInstruction {
   ///   0. `[writable]` Master Edition V1 key (pda of ['metadata', program id, mint id, 'edition'])
   ///   1. `[writable]` PDA for ReservationList of ['metadata', program id, master edition key, 'reservation', resource-key]
   ///   2. `[signer]` The resource you tied the reservation list too
+  #[cfg(feature = "deprecated")]
+  #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_state::SetReservationListArgsDef"))]
   DeprecatedSetReservationList(SetReservationListArgs),
 
   /// Create an empty reservation list for a resource who can come back later as a signer and fill the reservation list
@@ -142,6 +168,7 @@ pub enum MetadataInstruction {
   ///   5. `[]` Metadata key (pda of ['metadata', program id, mint id])
   ///   6. `[]` System program
   ///   7. `[]` Rent info
+  #[cfg(feature = "deprecated")]
   DeprecatedCreateReservationList,
 
   // Sign a piece of metadata that has you as an unverified creator so that it is now verified.
@@ -162,6 +189,8 @@ pub enum MetadataInstruction {
   ///   6. `[]` Master Edition V1 key (pda of ['metadata', program id, mint id, 'edition'])
   ///   7. `[]` Token program
   ///   8. `[]` Rent
+  #[cfg(feature = "deprecated")]
+  #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_state::MintPrintingTokensViaTokenArgsDef"))]
   DeprecatedMintPrintingTokensViaToken(MintPrintingTokensViaTokenArgs),
 
   /// Using your update authority, mint printing tokens for your master edition.
@@ -173,6 +202,8 @@ pub enum MetadataInstruction {
   ///   4. `[]` Master Edition V1 key (pda of ['metadata', program id, mint id, 'edition'])
   ///   5. `[]` Token program
   ///   6. `[]` Rent
+  #[cfg(feature = "deprecated")]
+  #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_state::MintPrintingTokensViaTokenArgsDef"))]
   DeprecatedMintPrintingTokens(MintPrintingTokensViaTokenArgs),
 
   /// Register a Metadata as a Master Edition V2, which means Edition V2s can be minted.
@@ -243,9 +274,679 @@ pub enum MetadataInstruction {
   /// so that it can be found using offset searches by the RPC to make client lookups cheaper.
   ///   0. `[writable]` Metadata account
   PuffMetadata,
+
+  /// Deprecated instruction decoded without the `deprecated` feature, `data` being its raw
+  /// arguments after the tag. Only the tags of `DEPRECATED_TAGS` can be encoded.
+  #[cfg(not(feature = "deprecated"))]
+  Deprecated { tag: u8, data: Vec<u8> },
+}
+
+/// Serde support for the state and deprecated argument types, which don't implement it.
+#[cfg(any(test, feature = "serde"))]
+mod serde_state {
+  use {
+      crate::state::{Creator, Data},
+      serde::{Deserialize, Deserializer, Serialize, Serializer},
+      solana_program::pubkey::Pubkey,
+  };
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(remote = "Creator")]
+  struct CreatorDef {
+      address: Pubkey,
+      verified: bool,
+      share: u8,
+  }
+
+  #[derive(Serialize, Deserialize)]
+  struct CreatorWrapper(#[serde(with = "CreatorDef")] Creator);
+
+  #[derive(Serialize, Deserialize)]
+  #[serde(remote = "Data")]
+  pub struct DataDef {
+      name: String,
+      symbol: String,
+      uri: String,
+      seller_fee_basis_points: u16,
+      #[serde(with = "creators")]
+      creators: Option<Vec<Creator>>,
+  }
+
+  #[derive(Serialize, Deserialize)]
+  struct DataWrapper(#[serde(with = "DataDef")] Data);
+
+  mod creators {
+      use super::*;
+
+      pub fn serialize<S: Serializer>(creators: &Option<Vec<Creator>>, serializer: S) -> Result<S::Ok, S::Error> {
+          let creators = creators.as_ref().map(|creators| creators.iter().cloned().map(CreatorWrapper).collect::<Vec<_>>());
+          creators.serialize(serializer)
+      }
+
+      pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Creator>>, D::Error> {
+          let creators = Option::<Vec<CreatorWrapper>>::deserialize(deserializer)?;
+          Ok(creators.map(|creators| creators.into_iter().map(|CreatorWrapper(creator)| creator).collect()))
+      }
+  }
+
+  pub mod option_data {
+      use super::*;
+
+      pub fn serialize<S: Serializer>(data: &Option<Data>, serializer: S) -> Result<S::Ok, S::Error> {
+          data.clone().map(DataWrapper).serialize(serializer)
+      }
+
+      pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Data>, D::Error> {
+          Ok(Option::<DataWrapper>::deserialize(deserializer)?.map(|DataWrapper(data)| data))
+      }
+  }
+
+  #[cfg(feature = "deprecated")]
+  pub use self::deprecated::*;
+
+  #[cfg(feature = "deprecated")]
+  mod deprecated {
+      use {
+          crate::deprecated_instruction::{MintPrintingTokensViaTokenArgs, Reservation, SetReservationListArgs},
+          serde::{Deserialize, Deserializer, Serialize, Serializer},
+          solana_program::pubkey::Pubkey,
+      };
+
+      #[derive(Serialize, Deserialize)]
+      #[serde(remote = "MintPrintingTokensViaTokenArgs")]
+      pub struct MintPrintingTokensViaTokenArgsDef {
+          supply: u64,
+      }
+
+      #[derive(Serialize, Deserialize)]
+      #[serde(remote = "Reservation")]
+      struct ReservationDef {
+          address: Pubkey,
+          spots_remaining: u64,
+          total_spots: u64,
+      }
+
+      #[derive(Serialize, Deserialize)]
+      struct ReservationWrapper(#[serde(with = "ReservationDef")] Reservation);
+
+      #[derive(Serialize, Deserialize)]
+      #[serde(remote = "SetReservationListArgs")]
+      pub struct SetReservationListArgsDef {
+          #[serde(with = "reservations")]
+          reservations: Vec<Reservation>,
+          total_reservation_spots: Option<u64>,
+          offset: u64,
+          total_spot_offset: u64,
+      }
+
+      mod reservations {
+          use super::*;
+
+          pub fn serialize<S: Serializer>(reservations: &[Reservation], serializer: S) -> Result<S::Ok, S::Error> {
+              serializer.collect_seq(reservations.iter().cloned().map(ReservationWrapper))
+          }
+
+          pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Reservation>, D::Error> {
+              let reservations = Vec::<ReservationWrapper>::deserialize(deserializer)?;
+              Ok(reservations.into_iter().map(|ReservationWrapper(reservation)| reservation).collect())
+          }
+      }
+  }
+}
+
+/// Tags of the variants behind the `deprecated` feature
+pub const DEPRECATED_TAGS: [u8; 6] = [2, 3, 5, 6, 8, 9];
+
+#[cfg(not(feature = "deprecated"))]
+impl BorshSerialize for MetadataInstruction {
+  fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+      if let Self::Deprecated { tag, .. } = self {
+          if !DEPRECATED_TAGS.contains(tag) {
+              return Err(std::io::Error::new(
+                  std::io::ErrorKind::InvalidInput,
+                  format!("Unexpected variant index: {}", tag),
+              ));
+          }
+      }
+      BorshSerialize::serialize(&self.discriminant(), writer)?;
+      match self {
+          Self::CreateMetadataAccount(args) => BorshSerialize::serialize(args, writer),
+          Self::UpdateMetadataAccount(args) => BorshSerialize::serialize(args, writer),
+          Self::CreateMasterEdition(args) => BorshSerialize::serialize(args, writer),
+          Self::MintNewEditionFromMasterEditionViaToken(args) => BorshSerialize::serialize(args, writer),
+          Self::MintNewEditionFromMasterEditionViaVaultProxy(args) => BorshSerialize::serialize(args, writer),
+          Self::UpdatePrimarySaleHappenedViaToken
+          | Self::SignMetadata
+          | Self::ConvertMasterEditionV1ToV2
+          | Self::PuffMetadata => Ok(()),
+          Self::Deprecated { data, .. } => writer.write_all(data),
+      }
+  }
+}
+
+#[cfg(not(feature = "deprecated"))]
+impl BorshDeserialize for MetadataInstruction {
+  fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+      let tag: u8 = BorshDeserialize::deserialize(buf)?;
+      Ok(match tag {
+          0 => Self::CreateMetadataAccount(BorshDeserialize::deserialize(buf)?),
+          1 => Self::UpdateMetadataAccount(BorshDeserialize::deserialize(buf)?),
+          4 => Self::UpdatePrimarySaleHappenedViaToken,
+          7 => Self::SignMetadata,
+          10 => Self::CreateMasterEdition(BorshDeserialize::deserialize(buf)?),
+          11 => Self::MintNewEditionFromMasterEditionViaToken(BorshDeserialize::deserialize(buf)?),
+          12 => Self::ConvertMasterEditionV1ToV2,
+          13 => Self::MintNewEditionFromMasterEditionViaVaultProxy(BorshDeserialize::deserialize(buf)?),
+          14 => Self::PuffMetadata,
+          _ if DEPRECATED_TAGS.contains(&tag) => {
+              let data = buf.to_vec();
+              *buf = &[];
+              Self::Deprecated { tag, data }
+          }
+          _ => {
+              return Err(std::io::Error::new(
+                  std::io::ErrorKind::InvalidInput,
+                  format!("Unexpected variant index: {}", tag),
+              ))
+          }
+      })
+  }
+}
+
+impl ProgramInstruction for MetadataInstruction {
+  fn discriminant(&self) -> u8 {
+      match self {
+          Self::CreateMetadataAccount(_) => 0,
+          Self::UpdateMetadataAccount(_) => 1,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateMasterEdition(_) => 2,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintNewEditionFromMasterEditionViaPrintingToken => 3,
+          Self::UpdatePrimarySaleHappenedViaToken => 4,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedSetReservationList(_) => 5,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateReservationList => 6,
+          Self::SignMetadata => 7,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokensViaToken(_) => 8,
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokens(_) => 9,
+          Self::CreateMasterEdition(_) => 10,
+          Self::MintNewEditionFromMasterEditionViaToken(_) => 11,
+          Self::ConvertMasterEditionV1ToV2 => 12,
+          Self::MintNewEditionFromMasterEditionViaVaultProxy(_) => 13,
+          Self::PuffMetadata => 14,
+          #[cfg(not(feature = "deprecated"))]
+          Self::Deprecated { tag, .. } => *tag,
+      }
+  }
+
+  fn variant_name(&self) -> &'static str {
+      match self {
+          Self::CreateMetadataAccount(_) => "CreateMetadataAccount",
+          Self::UpdateMetadataAccount(_) => "UpdateMetadataAccount",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateMasterEdition(_) => "DeprecatedCreateMasterEdition",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintNewEditionFromMasterEditionViaPrintingToken => {
+              "DeprecatedMintNewEditionFromMasterEditionViaPrintingToken"
+          }
+          Self::UpdatePrimarySaleHappenedViaToken => "UpdatePrimarySaleHappenedViaToken",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedSetReservationList(_) => "DeprecatedSetReservationList",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateReservationList => "DeprecatedCreateReservationList",
+          Self::SignMetadata => "SignMetadata",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokensViaToken(_) => "DeprecatedMintPrintingTokensViaToken",
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokens(_) => "DeprecatedMintPrintingTokens",
+          Self::CreateMasterEdition(_) => "CreateMasterEdition",
+          Self::MintNewEditionFromMasterEditionViaToken(_) => "MintNewEditionFromMasterEditionViaToken",
+          Self::ConvertMasterEditionV1ToV2 => "ConvertMasterEditionV1ToV2",
+          Self::MintNewEditionFromMasterEditionViaVaultProxy(_) => {
+              "MintNewEditionFromMasterEditionViaVaultProxy"
+          }
+          Self::PuffMetadata => "PuffMetadata",
+          #[cfg(not(feature = "deprecated"))]
+          Self::Deprecated { tag, .. } => match tag {
+              2 => "deprecated instruction 2",
+              3 => "deprecated instruction 3",
+              5 => "deprecated instruction 5",
+              6 => "deprecated instruction 6",
+              8 => "deprecated instruction 8",
+              9 => "deprecated instruction 9",
+              _ => "deprecated instruction",
+          },
+      }
+  }
+
+  fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+      self.try_to_vec()
+          .map_err(|e| ProgramError::BorshIoError(e.to_string()))
+  }
+
+  fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+      Self::try_from_slice(input).map_err(|_| ProgramError::InvalidInstructionData)
+  }
+
+  fn expected_account_count(&self) -> AccountCount {
+      match self {
+          Self::CreateMetadataAccount(_) => AccountCount::exactly(7),
+          Self::UpdateMetadataAccount(_) => AccountCount::exactly(2),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateMasterEdition(_) => AccountCount::between(12, 13),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintNewEditionFromMasterEditionViaPrintingToken => {
+              AccountCount::between(15, 16)
+          }
+          Self::UpdatePrimarySaleHappenedViaToken => AccountCount::exactly(3),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedSetReservationList(_) => AccountCount::exactly(3),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedCreateReservationList => AccountCount::exactly(8),
+          Self::SignMetadata => AccountCount::exactly(2),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokensViaToken(_) => AccountCount::exactly(9),
+          #[cfg(feature = "deprecated")]
+          Self::DeprecatedMintPrintingTokens(_) => AccountCount::exactly(7),
+          Self::CreateMasterEdition(_) => AccountCount::exactly(9),
+          Self::MintNewEditionFromMasterEditionViaToken(_) => AccountCount::exactly(14),
+          Self::ConvertMasterEditionV1ToV2 => AccountCount::exactly(3),
+          Self::MintNewEditionFromMasterEditionViaVaultProxy(_) => AccountCount::exactly(17),
+          Self::PuffMetadata => AccountCount::exactly(1),
+          #[cfg(not(feature = "deprecated"))]
+          Self::Deprecated { tag, .. } => match tag {
+              2 => AccountCount::between(12, 13),
+              3 => AccountCount::between(15, 16),
+              5 => AccountCount::exactly(3),
+              6 => AccountCount::exactly(8),
+              8 => AccountCount::exactly(9),
+              _ => AccountCount::exactly(7),
+          },
+      }
+  }
+
+  fn abi() -> ProgramAbi {
+      let readonly = AccountAbi::readonly;
+      let writable = AccountAbi::writable;
+      let signer = |name: &str| AccountAbi::readonly(name).signer();
+      let payer = || AccountAbi::writable("payer").signer();
+      let master_edition_args = || vec![FieldAbi::new("max_supply", "Option<u64>")];
+      let printing_args = || vec![FieldAbi::new("supply", "u64")];
+      let edition_args = || vec![FieldAbi::new("edition", "u64")];
+      ProgramAbi {
+          instructions: vec![
+              InstructionAbi::new(
+                  0,
+                  "CreateMetadataAccount",
+                  vec![FieldAbi::new("data", "Data"), FieldAbi::new("is_mutable", "bool")],
+                  vec![
+                      writable("metadata_account"),
+                      readonly("mint"),
+                      signer("mint_authority"),
+                      payer(),
+                      readonly("update_authority"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("CreateMetadataAccountArgs")
+              .op_name("create_metadata_accounts"),
+              InstructionAbi::new(
+                  1,
+                  "UpdateMetadataAccount",
+                  vec![
+                      FieldAbi::new("data", "Option<Data>"),
+                      FieldAbi::new("update_authority", "Option<Pubkey>"),
+                      FieldAbi::new("primary_sale_happened", "Option<bool>"),
+                  ],
+                  vec![writable("metadata_account"), signer("update_authority")],
+              )
+              .inputs_name("UpdateMetadataAccountArgs")
+              .op_name("update_metadata_accounts"),
+              InstructionAbi::new(
+                  2,
+                  "DeprecatedCreateMasterEdition",
+                  master_edition_args(),
+                  vec![
+                      writable("edition"),
+                      writable("mint"),
+                      writable("printing_mint"),
+                      writable("one_time_printing_authorization_mint"),
+                      signer("update_authority"),
+                      signer("printing_mint_authority"),
+                      signer("mint_authority"),
+                      readonly("metadata"),
+                      payer(),
+                      readonly("token_program"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                      signer("one_time_printing_authorization_mint_authority").optional(),
+                  ],
+              )
+              .inputs_name("CreateMasterEditionArgs"),
+              InstructionAbi::new(
+                  3,
+                  "DeprecatedMintNewEditionFromMasterEditionViaPrintingToken",
+                  vec![],
+                  vec![
+                      writable("new_metadata"),
+                      writable("new_edition"),
+                      writable("master_edition"),
+                      writable("new_mint"),
+                      signer("new_mint_authority"),
+                      writable("printing_mint"),
+                      writable("master_token_account"),
+                      writable("edition_marker"),
+                      signer("burn_authority"),
+                      payer(),
+                      readonly("new_metadata_update_authority"),
+                      readonly("metadata"),
+                      readonly("token_program"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                      writable("reservation_list").optional(),
+                  ],
+              ),
+              InstructionAbi::new(
+                  4,
+                  "UpdatePrimarySaleHappenedViaToken",
+                  vec![],
+                  vec![writable("metadata"), signer("owner"), readonly("token")],
+              )
+              .op_name("update_primary_sale_happened_via_token"),
+              InstructionAbi::new(
+                  5,
+                  "DeprecatedSetReservationList",
+                  vec![
+                      FieldAbi::new("reservations", "Vec<Reservation>"),
+                      FieldAbi::new("total_reservation_spots", "Option<u64>"),
+                      FieldAbi::new("offset", "u64"),
+                      FieldAbi::new("total_spot_offset", "u64"),
+                  ],
+                  vec![writable("master_edition"), writable("reservation_list"), signer("resource")],
+              )
+              .inputs_name("SetReservationListArgs"),
+              InstructionAbi::new(
+                  6,
+                  "DeprecatedCreateReservationList",
+                  vec![],
+                  vec![
+                      writable("reservation_list"),
+                      payer(),
+                      signer("update_authority"),
+                      readonly("master_edition"),
+                      readonly("resource"),
+                      readonly("metadata"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                  ],
+              ),
+              InstructionAbi::new(7, "SignMetadata", vec![], vec![writable("metadata"), signer("creator")])
+                  .op_name("sign_metadata"),
+              InstructionAbi::new(
+                  8,
+                  "DeprecatedMintPrintingTokensViaToken",
+                  printing_args(),
+                  vec![
+                      writable("destination"),
+                      writable("token"),
+                      writable("one_time_printing_authorization_mint"),
+                      writable("printing_mint"),
+                      signer("burn_authority"),
+                      readonly("metadata"),
+                      readonly("master_edition"),
+                      readonly("token_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("MintPrintingTokensViaTokenArgs"),
+              InstructionAbi::new(
+                  9,
+                  "DeprecatedMintPrintingTokens",
+                  printing_args(),
+                  vec![
+                      writable("destination"),
+                      writable("printing_mint"),
+                      signer("update_authority"),
+                      readonly("metadata"),
+                      readonly("master_edition"),
+                      readonly("token_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("MintPrintingTokensViaTokenArgs"),
+              InstructionAbi::new(
+                  10,
+                  "CreateMasterEdition",
+                  master_edition_args(),
+                  vec![
+                      writable("edition"),
+                      writable("mint"),
+                      signer("update_authority"),
+                      signer("mint_authority"),
+                      payer(),
+                      readonly("metadata"),
+                      readonly("token_program"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("CreateMasterEditionArgs")
+              .op_name("create_master_edition"),
+              InstructionAbi::new(
+                  11,
+                  "MintNewEditionFromMasterEditionViaToken",
+                  edition_args(),
+                  vec![
+                      writable("new_metadata"),
+                      writable("new_edition"),
+                      writable("master_edition"),
+                      writable("new_mint"),
+                      writable("edition_mark_pda"),
+                      signer("new_mint_authority"),
+                      payer(),
+                      signer("token_account_owner"),
+                      readonly("token_account"),
+                      readonly("new_metadata_update_authority"),
+                      readonly("metadata"),
+                      readonly("token_program"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("MintNewEditionFromMasterEditionViaTokenArgs")
+              .op_name("mint_new_edition_from_master_edition_via_token"),
+              InstructionAbi::new(
+                  12,
+                  "ConvertMasterEditionV1ToV2",
+                  vec![],
+                  vec![writable("master_edition"), writable("one_time_auth"), writable("printing_mint")],
+              )
+              .op_name("convert_master_edition_v1_to_v2"),
+              InstructionAbi::new(
+                  13,
+                  "MintNewEditionFromMasterEditionViaVaultProxy",
+                  edition_args(),
+                  vec![
+                      writable("new_metadata"),
+                      writable("new_edition"),
+                      writable("master_edition"),
+                      writable("new_mint"),
+                      writable("edition_mark_pda"),
+                      signer("new_mint_authority"),
+                      payer(),
+                      signer("vault_authority"),
+                      readonly("safety_deposit_store"),
+                      readonly("safety_deposit_box"),
+                      readonly("vault"),
+                      readonly("new_metadata_update_authority"),
+                      readonly("metadata"),
+                      readonly("token_program"),
+                      readonly("token_vault_program_info"),
+                      readonly("system_program"),
+                      readonly("rent"),
+                  ],
+              )
+              .inputs_name("MintNewEditionFromMasterEditionViaTokenArgs")
+              .op_name("mint_edition_from_master_edition_via_vault_proxy"),
+              InstructionAbi::new(14, "PuffMetadata", vec![], vec![writable("metadata_account")])
+                  .op_name("puff_metadata_account"),
+          ],
+          types: vec![
+              TypeAbi::new_struct(
+                  "Data",
+                  vec![
+                      FieldAbi::new("name", "String"),
+                      FieldAbi::new("symbol", "String"),
+                      FieldAbi::new("uri", "String"),
+                      FieldAbi::new("seller_fee_basis_points", "u16"),
+                      FieldAbi::new("creators", "Option<Vec<Creator>>"),
+                  ],
+              ),
+              TypeAbi::new_struct(
+                  "Creator",
+                  vec![
+                      FieldAbi::new("address", "Pubkey"),
+                      FieldAbi::new("verified", "bool"),
+                      FieldAbi::new("share", "u8"),
+                  ],
+              ),
+              TypeAbi::new_struct(
+                  "Reservation",
+                  vec![
+                      FieldAbi::new("address", "Pubkey"),
+                      FieldAbi::new("spots_remaining", "u64"),
+                      FieldAbi::new("total_spots", "u64"),
+                  ],
+              ),
+          ],
+      }
+  }
+}
+
+/// Errors of the fallible instruction builders
+#[derive(Debug)]
+pub enum MetadataBuildError {
+  /// The instruction data could not be serialized
+  Serialization(std::io::Error),
+  /// The metadata would be rejected by the program, every broken rule is listed
+  InvalidData(Vec<DataError>),
+}
+
+impl std::fmt::Display for MetadataBuildError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+      match self {
+          MetadataBuildError::Serialization(err) => write!(f, "failed to serialize instruction data: {}", err),
+          MetadataBuildError::InvalidData(errors) => {
+              write!(f, "invalid metadata")?;
+              for (index, err) in errors.iter().enumerate() {
+                  write!(f, "{} {}", if index == 0 { ":" } else { "," }, err)?;
+              }
+              Ok(())
+          }
+      }
+  }
+}
+
+impl std::error::Error for MetadataBuildError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+      match self {
+          MetadataBuildError::Serialization(err) => Some(err),
+          MetadataBuildError::InvalidData(_) => None,
+      }
+  }
+}
+
+/// A rule of the metadata program broken by a [Data](../state/struct.Data.html)
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataError {
+  /// `name` is longer than MAX_NAME_LENGTH bytes
+  NameTooLong(usize),
+  /// `symbol` is longer than MAX_SYMBOL_LENGTH bytes
+  SymbolTooLong(usize),
+  /// `uri` is longer than MAX_URI_LENGTH bytes
+  UriTooLong(usize),
+  /// `seller_fee_basis_points` is above 10000
+  InvalidBasisPoints(u16),
+  /// `creators` is set but empty
+  NoCreators,
+  /// `creators` has more than MAX_CREATOR_LIMIT entries
+  TooManyCreators(usize),
+  /// The address is listed by more than one creator
+  DuplicateCreator(Pubkey),
+  /// The creator shares don't add up to 100
+  InvalidShareTotal(u64),
+}
+
+impl std::fmt::Display for DataError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+      match self {
+          DataError::NameTooLong(len) => write!(f, "name is {} bytes, max {}", len, MAX_NAME_LENGTH),
+          DataError::SymbolTooLong(len) => write!(f, "symbol is {} bytes, max {}", len, MAX_SYMBOL_LENGTH),
+          DataError::UriTooLong(len) => write!(f, "uri is {} bytes, max {}", len, MAX_URI_LENGTH),
+          DataError::InvalidBasisPoints(points) => {
+              write!(f, "seller fee basis points {} is above 10000", points)
+          }
+          DataError::NoCreators => write!(f, "creators must be at least one"),
+          DataError::TooManyCreators(len) => write!(f, "{} creators, max {}", len, MAX_CREATOR_LIMIT),
+          DataError::DuplicateCreator(address) => write!(f, "creator {} is listed twice", address),
+          DataError::InvalidShareTotal(total) => write!(f, "creator shares add up to {} instead of 100", total),
+      }
+  }
+}
+
+/// Checks `data` against the rules the program enforces, returns every broken rule
+pub fn validate_data(data: &Data) -> Result<(), Vec<DataError>> {
+  let mut errors = vec![];
+  if data.name.len() > MAX_NAME_LENGTH {
+      errors.push(DataError::NameTooLong(data.name.len()));
+  }
+  if data.symbol.len() > MAX_SYMBOL_LENGTH {
+      errors.push(DataError::SymbolTooLong(data.symbol.len()));
+  }
+  if data.uri.len() > MAX_URI_LENGTH {
+      errors.push(DataError::UriTooLong(data.uri.len()));
+  }
+  if data.seller_fee_basis_points > 10000 {
+      errors.push(DataError::InvalidBasisPoints(data.seller_fee_basis_points));
+  }
+  if let Some(creators) = &data.creators {
+      if creators.is_empty() {
+          errors.push(DataError::NoCreators);
+      }
+      if creators.len() > MAX_CREATOR_LIMIT {
+          errors.push(DataError::TooManyCreators(creators.len()));
+      }
+      for (index, creator) in creators.iter().enumerate() {
+          let duplicate = DataError::DuplicateCreator(creator.address);
+          if creators[..index].iter().any(|other| other.address == creator.address) && !errors.contains(&duplicate) {
+              errors.push(duplicate);
+          }
+      }
+      let total: u64 = creators.iter().map(|creator| creator.share as u64).sum();
+      if !creators.is_empty() && total != 100 {
+          errors.push(DataError::InvalidShareTotal(total));
+      }
+  }
+  if errors.is_empty() {
+      Ok(())
+  } else {
+      Err(errors)
+  }
+}
+
+impl From<std::io::Error> for MetadataBuildError {
+  fn from(err: std::io::Error) -> Self {
+      MetadataBuildError::Serialization(err)
+  }
 }
 
 /// Creates an CreateMetadataAccounts instruction
+///
+/// The metadata is not validated, see [try_create_metadata_accounts](fn.try_create_metadata_accounts.html)
 #[allow(clippy::too_many_arguments)]
 pub fn create_metadata_accounts(
   program_id: Pubkey,
@@ -288,7 +989,52 @@ pub fn create_metadata_accounts(
   }
 }
 
+/// Creates an CreateMetadataAccounts instruction, failing when the metadata is rejected by
+/// [validate_data](fn.validate_data.html)
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_metadata_accounts(
+  program_id: Pubkey,
+  metadata_account: Pubkey,
+  mint: Pubkey,
+  mint_authority: Pubkey,
+  payer: Pubkey,
+  update_authority: Pubkey,
+  name: String,
+  symbol: String,
+  uri: String,
+  creators: Option<Vec<Creator>>,
+  seller_fee_basis_points: u16,
+  update_authority_is_signer: bool,
+  is_mutable: bool,
+) -> Result<Instruction, MetadataBuildError> {
+  let metadata = Data {
+      name,
+      symbol,
+      uri,
+      seller_fee_basis_points,
+      creators,
+  };
+  validate_data(&metadata).map_err(MetadataBuildError::InvalidData)?;
+  Ok(create_metadata_accounts(
+      program_id,
+      metadata_account,
+      mint,
+      mint_authority,
+      payer,
+      update_authority,
+      metadata.name,
+      metadata.symbol,
+      metadata.uri,
+      metadata.creators,
+      metadata.seller_fee_basis_points,
+      update_authority_is_signer,
+      is_mutable,
+  ))
+}
+
 /// update metadata account instruction
+///
+/// The new metadata is not validated, see [try_update_metadata_accounts](fn.try_update_metadata_accounts.html)
 pub fn update_metadata_accounts(
   program_id: Pubkey,
   metadata_account: Pubkey,
@@ -313,13 +1059,47 @@ pub fn update_metadata_accounts(
   }
 }
 
+/// update metadata account instruction, failing when the new metadata is rejected by
+/// [validate_data](fn.validate_data.html)
+pub fn try_update_metadata_accounts(
+  program_id: Pubkey,
+  metadata_account: Pubkey,
+  update_authority: Pubkey,
+  new_update_authority: Option<Pubkey>,
+  new_data: Option<Data>,
+  primary_sale_happened: Option<bool>,
+) -> Result<Instruction, MetadataBuildError> {
+  if let Some(new_data) = &new_data {
+      validate_data(new_data).map_err(MetadataBuildError::InvalidData)?;
+  }
+  Ok(update_metadata_accounts(
+      program_id,
+      metadata_account,
+      update_authority,
+      new_update_authority,
+      new_data,
+      primary_sale_happened,
+  ))
+}
+
 /// puff metadata account instruction
 pub fn puff_metadata_account(program_id: Pubkey, metadata_account: Pubkey) -> Instruction {
-  Instruction {
+  try_puff_metadata_account(program_id, metadata_account).unwrap()
+}
+
+/// puff metadata account instruction
+pub fn try_puff_metadata_account(
+  program_id: Pubkey,
+  metadata_account: Pubkey,
+) -> Result<Instruction, MetadataBuildError> {
+  let accounts = vec![AccountMeta::new(metadata_account, false)];
+  let data = MetadataInstruction::PuffMetadata.try_to_vec()?;
+
+  Ok(Instruction {
       program_id,
-      accounts: vec![AccountMeta::new(metadata_account, false)],
-      data: MetadataInstruction::PuffMetadata.try_to_vec().unwrap(),
-  }
+      accounts,
+      data,
+  })
 }
 
 /// creates a update_primary_sale_happened_via_token instruction
@@ -330,17 +1110,28 @@ pub fn update_primary_sale_happened_via_token(
   owner: Pubkey,
   token: Pubkey,
 ) -> Instruction {
-  Instruction {
+  try_update_primary_sale_happened_via_token(program_id, metadata, owner, token).unwrap()
+}
+
+/// creates a update_primary_sale_happened_via_token instruction
+pub fn try_update_primary_sale_happened_via_token(
+  program_id: Pubkey,
+  metadata: Pubkey,
+  owner: Pubkey,
+  token: Pubkey,
+) -> Result<Instruction, MetadataBuildError> {
+  let accounts = vec![
+      AccountMeta::new(metadata, false),
+      AccountMeta::new_readonly(owner, true),
+      AccountMeta::new_readonly(token, false),
+  ];
+  let data = MetadataInstruction::UpdatePrimarySaleHappenedViaToken.try_to_vec()?;
+
+  Ok(Instruction {
       program_id,
-      accounts: vec![
-          AccountMeta::new(metadata, false),
-          AccountMeta::new_readonly(owner, true),
-          AccountMeta::new_readonly(token, false),
-      ],
-      data: MetadataInstruction::UpdatePrimarySaleHappenedViaToken
-          .try_to_vec()
-          .unwrap(),
-  }
+      accounts,
+      data,
+  })
 }
 
 /// creates a create_master_edition instruction
@@ -355,6 +1146,31 @@ pub fn create_master_edition(
   payer: Pubkey,
   max_supply: Option<u64>,
 ) -> Instruction {
+  try_create_master_edition(
+      program_id,
+      edition,
+      mint,
+      update_authority,
+      mint_authority,
+      metadata,
+      payer,
+      max_supply,
+  )
+  .unwrap()
+}
+
+/// creates a create_master_edition instruction
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_master_edition(
+  program_id: Pubkey,
+  edition: Pubkey,
+  mint: Pubkey,
+  update_authority: Pubkey,
+  mint_authority: Pubkey,
+  metadata: Pubkey,
+  payer: Pubkey,
+  max_supply: Option<u64>,
+) -> Result<Instruction, MetadataBuildError> {
   let accounts = vec![
       AccountMeta::new(edition, false),
       AccountMeta::new(mint, false),
@@ -366,14 +1182,13 @@ pub fn create_master_edition(
       AccountMeta::new_readonly(solana_program::system_program::id(), false),
       AccountMeta::new_readonly(sysvar::rent::id(), false),
   ];
+  let data = MetadataInstruction::CreateMasterEdition(CreateMasterEditionArgs { max_supply }).try_to_vec()?;
 
-  Instruction {
+  Ok(Instruction {
       program_id,
       accounts,
-      data: MetadataInstruction::CreateMasterEdition(CreateMasterEditionArgs { max_supply })
-          .try_to_vec()
-          .unwrap(),
-  }
+      data,
+  })
 }
 
 /// creates a mint_new_edition_from_master_edition instruction
@@ -393,18 +1208,42 @@ pub fn mint_new_edition_from_master_edition_via_token(
   metadata_mint: Pubkey,
   edition: u64,
 ) -> Instruction {
-  let edition_number = edition.checked_div(EDITION_MARKER_BIT_SIZE).unwrap();
-  let as_string = edition_number.to_string();
-  let (edition_mark_pda, _) = Pubkey::find_program_address(
-      &[
-          PREFIX.as_bytes(),
-          program_id.as_ref(),
-          metadata_mint.as_ref(),
-          EDITION.as_bytes(),
-          as_string.as_bytes(),
-      ],
-      &program_id,
-  );
+  try_mint_new_edition_from_master_edition_via_token(
+      program_id,
+      new_metadata,
+      new_edition,
+      master_edition,
+      new_mint,
+      new_mint_authority,
+      payer,
+      token_account_owner,
+      token_account,
+      new_metadata_update_authority,
+      metadata,
+      metadata_mint,
+      edition,
+  )
+  .unwrap()
+}
+
+/// creates a mint_new_edition_from_master_edition instruction
+#[allow(clippy::too_many_arguments)]
+pub fn try_mint_new_edition_from_master_edition_via_token(
+  program_id: Pubkey,
+  new_metadata: Pubkey,
+  new_edition: Pubkey,
+  master_edition: Pubkey,
+  new_mint: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  token_account_owner: Pubkey,
+  token_account: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  metadata: Pubkey,
+  metadata_mint: Pubkey,
+  edition: u64,
+) -> Result<Instruction, MetadataBuildError> {
+  let (edition_mark_pda, _) = pda::find_edition_marker_account(&program_id, &metadata_mint, edition);
 
   let accounts = vec![
       AccountMeta::new(new_metadata, false),
@@ -422,16 +1261,16 @@ pub fn mint_new_edition_from_master_edition_via_token(
       AccountMeta::new_readonly(solana_program::system_program::id(), false),
       AccountMeta::new_readonly(sysvar::rent::id(), false),
   ];
+  let data = MetadataInstruction::MintNewEditionFromMasterEditionViaToken(
+      MintNewEditionFromMasterEditionViaTokenArgs { edition },
+  )
+  .try_to_vec()?;
 
-  Instruction {
+  Ok(Instruction {
       program_id,
       accounts,
-      data: MetadataInstruction::MintNewEditionFromMasterEditionViaToken(
-          MintNewEditionFromMasterEditionViaTokenArgs { edition },
-      )
-      .try_to_vec()
-      .unwrap(),
-  }
+      data,
+  })
 }
 
 /// Sign Metadata
@@ -441,16 +1280,26 @@ pub fn sign_metadata(
   metadata: Pubkey,
   creator: Pubkey,
 ) -> Instruction {
-  Instruction {
+  try_sign_metadata(program_id, metadata, creator).unwrap()
+}
+
+/// Sign Metadata
+pub fn try_sign_metadata(
+  program_id: Pubkey,
+  metadata: Pubkey,
+  creator: Pubkey,
+) -> Result<Instruction, MetadataBuildError> {
+  let accounts = vec![
+      AccountMeta::new(metadata, false),
+      AccountMeta::new_readonly(creator, true),
+  ];
+  let data = MetadataInstruction::SignMetadata.try_to_vec()?;
+
+  Ok(Instruction {
       program_id,
-      accounts: vec![
-          AccountMeta::new(metadata, false),
-          AccountMeta::new_readonly(creator, true),
-      ],
-      data: MetadataInstruction::SignMetadata
-          .try_to_vec()
-          .unwrap(),
-  }
+      accounts,
+      data,
+  })
 }
 
 /// Converts a master edition v1 to v2
@@ -461,17 +1310,28 @@ pub fn convert_master_edition_v1_to_v2(
   one_time_auth: Pubkey,
   printing_mint: Pubkey,
 ) -> Instruction {
-  Instruction {
+  try_convert_master_edition_v1_to_v2(program_id, master_edition, one_time_auth, printing_mint).unwrap()
+}
+
+/// Converts a master edition v1 to v2
+pub fn try_convert_master_edition_v1_to_v2(
+  program_id: Pubkey,
+  master_edition: Pubkey,
+  one_time_auth: Pubkey,
+  printing_mint: Pubkey,
+) -> Result<Instruction, MetadataBuildError> {
+  let accounts = vec![
+      AccountMeta::new(master_edition, false),
+      AccountMeta::new(one_time_auth, false),
+      AccountMeta::new(printing_mint, false),
+  ];
+  let data = MetadataInstruction::ConvertMasterEditionV1ToV2.try_to_vec()?;
+
+  Ok(Instruction {
       program_id,
-      accounts: vec![
-          AccountMeta::new(master_edition, false),
-          AccountMeta::new(one_time_auth, false),
-          AccountMeta::new(printing_mint, false),
-      ],
-      data: MetadataInstruction::ConvertMasterEditionV1ToV2
-          .try_to_vec()
-          .unwrap(),
-  }
+      accounts,
+      data,
+  })
 }
 
 /// creates a mint_edition_proxy instruction
@@ -495,6 +1355,49 @@ pub fn mint_edition_from_master_edition_via_vault_proxy(
   token_vault_program_info: Pubkey,
   edition: u64,
 ) -> Instruction {
+  try_mint_edition_from_master_edition_via_vault_proxy(
+      program_id,
+      new_metadata,
+      new_edition,
+      master_edition,
+      new_mint,
+      edition_mark_pda,
+      new_mint_authority,
+      payer,
+      vault_authority,
+      safety_deposit_store,
+      safety_deposit_box,
+      vault,
+      new_metadata_update_authority,
+      metadata,
+      token_program,
+      token_vault_program_info,
+      edition,
+  )
+  .unwrap()
+}
+
+/// creates a mint_edition_proxy instruction
+#[allow(clippy::too_many_arguments)]
+pub fn try_mint_edition_from_master_edition_via_vault_proxy(
+  program_id: Pubkey,
+  new_metadata: Pubkey,
+  new_edition: Pubkey,
+  master_edition: Pubkey,
+  new_mint: Pubkey,
+  edition_mark_pda: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  vault_authority: Pubkey,
+  safety_deposit_store: Pubkey,
+  safety_deposit_box: Pubkey,
+  vault: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  metadata: Pubkey,
+  token_program: Pubkey,
+  token_vault_program_info: Pubkey,
+  edition: u64,
+) -> Result<Instruction, MetadataBuildError> {
   let accounts = vec![
       AccountMeta::new(new_metadata, false),
       AccountMeta::new(new_edition, false),
@@ -514,14 +1417,541 @@ pub fn mint_edition_from_master_edition_via_vault_proxy(
       AccountMeta::new_readonly(solana_program::system_program::id(), false),
       AccountMeta::new_readonly(sysvar::rent::id(), false),
   ];
+  let data = MetadataInstruction::MintNewEditionFromMasterEditionViaVaultProxy(
+      MintNewEditionFromMasterEditionViaTokenArgs { edition },
+  )
+  .try_to_vec()?;
 
-  Instruction {
+  Ok(Instruction {
       program_id,
       accounts,
-      data: MetadataInstruction::MintNewEditionFromMasterEditionViaVaultProxy(
-          MintNewEditionFromMasterEditionViaTokenArgs { edition },
+      data,
+  })
+}
+
+/// Creates an CreateMetadataAccounts instruction for the metadata account of `mint`
+#[allow(clippy::too_many_arguments)]
+pub fn create_metadata_accounts_for_mint(
+  program_id: Pubkey,
+  mint: Pubkey,
+  mint_authority: Pubkey,
+  payer: Pubkey,
+  update_authority: Pubkey,
+  name: String,
+  symbol: String,
+  uri: String,
+  creators: Option<Vec<Creator>>,
+  seller_fee_basis_points: u16,
+  update_authority_is_signer: bool,
+  is_mutable: bool,
+) -> Instruction {
+  try_create_metadata_accounts_for_mint(
+      program_id,
+      mint,
+      mint_authority,
+      payer,
+      update_authority,
+      name,
+      symbol,
+      uri,
+      creators,
+      seller_fee_basis_points,
+      update_authority_is_signer,
+      is_mutable,
+  )
+  .unwrap()
+}
+
+/// Creates an CreateMetadataAccounts instruction for the metadata account of `mint`
+#[allow(clippy::too_many_arguments)]
+pub fn try_create_metadata_accounts_for_mint(
+  program_id: Pubkey,
+  mint: Pubkey,
+  mint_authority: Pubkey,
+  payer: Pubkey,
+  update_authority: Pubkey,
+  name: String,
+  symbol: String,
+  uri: String,
+  creators: Option<Vec<Creator>>,
+  seller_fee_basis_points: u16,
+  update_authority_is_signer: bool,
+  is_mutable: bool,
+) -> Result<Instruction, MetadataBuildError> {
+  let (metadata_account, _) = pda::find_metadata_account(&program_id, &mint);
+  try_create_metadata_accounts(
+      program_id,
+      metadata_account,
+      mint,
+      mint_authority,
+      payer,
+      update_authority,
+      name,
+      symbol,
+      uri,
+      creators,
+      seller_fee_basis_points,
+      update_authority_is_signer,
+      is_mutable,
+  )
+}
+
+/// creates a create_master_edition instruction for the master edition of `mint`
+pub fn create_master_edition_for_mint(
+  program_id: Pubkey,
+  mint: Pubkey,
+  update_authority: Pubkey,
+  mint_authority: Pubkey,
+  payer: Pubkey,
+  max_supply: Option<u64>,
+) -> Instruction {
+  try_create_master_edition_for_mint(
+      program_id,
+      mint,
+      update_authority,
+      mint_authority,
+      payer,
+      max_supply,
+  )
+  .unwrap()
+}
+
+/// creates a create_master_edition instruction for the master edition of `mint`
+pub fn try_create_master_edition_for_mint(
+  program_id: Pubkey,
+  mint: Pubkey,
+  update_authority: Pubkey,
+  mint_authority: Pubkey,
+  payer: Pubkey,
+  max_supply: Option<u64>,
+) -> Result<Instruction, MetadataBuildError> {
+  let (edition, _) = pda::find_master_edition_account(&program_id, &mint);
+  let (metadata, _) = pda::find_metadata_account(&program_id, &mint);
+  try_create_master_edition(
+      program_id,
+      edition,
+      mint,
+      update_authority,
+      mint_authority,
+      metadata,
+      payer,
+      max_supply,
+  )
+}
+
+/// creates a mint_new_edition_from_master_edition instruction printing `edition` of `master_mint` into `new_mint`
+#[allow(clippy::too_many_arguments)]
+pub fn mint_new_edition_from_master_edition_via_token_for_mint(
+  program_id: Pubkey,
+  new_mint: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  token_account_owner: Pubkey,
+  token_account: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  master_mint: Pubkey,
+  edition: u64,
+) -> Instruction {
+  try_mint_new_edition_from_master_edition_via_token_for_mint(
+      program_id,
+      new_mint,
+      new_mint_authority,
+      payer,
+      token_account_owner,
+      token_account,
+      new_metadata_update_authority,
+      master_mint,
+      edition,
+  )
+  .unwrap()
+}
+
+/// creates a mint_new_edition_from_master_edition instruction printing `edition` of `master_mint` into `new_mint`
+#[allow(clippy::too_many_arguments)]
+pub fn try_mint_new_edition_from_master_edition_via_token_for_mint(
+  program_id: Pubkey,
+  new_mint: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  token_account_owner: Pubkey,
+  token_account: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  master_mint: Pubkey,
+  edition: u64,
+) -> Result<Instruction, MetadataBuildError> {
+  let (new_metadata, _) = pda::find_metadata_account(&program_id, &new_mint);
+  let (new_edition, _) = pda::find_master_edition_account(&program_id, &new_mint);
+  let (master_edition, _) = pda::find_master_edition_account(&program_id, &master_mint);
+  let (metadata, _) = pda::find_metadata_account(&program_id, &master_mint);
+  try_mint_new_edition_from_master_edition_via_token(
+      program_id,
+      new_metadata,
+      new_edition,
+      master_edition,
+      new_mint,
+      new_mint_authority,
+      payer,
+      token_account_owner,
+      token_account,
+      new_metadata_update_authority,
+      metadata,
+      master_mint,
+      edition,
+  )
+}
+
+/// creates a mint_edition_proxy instruction printing `edition` of `master_mint` into `new_mint`
+#[allow(clippy::too_many_arguments)]
+pub fn mint_edition_from_master_edition_via_vault_proxy_for_mint(
+  program_id: Pubkey,
+  new_mint: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  vault_authority: Pubkey,
+  safety_deposit_store: Pubkey,
+  safety_deposit_box: Pubkey,
+  vault: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  master_mint: Pubkey,
+  token_program: Pubkey,
+  token_vault_program_info: Pubkey,
+  edition: u64,
+) -> Instruction {
+  try_mint_edition_from_master_edition_via_vault_proxy_for_mint(
+      program_id,
+      new_mint,
+      new_mint_authority,
+      payer,
+      vault_authority,
+      safety_deposit_store,
+      safety_deposit_box,
+      vault,
+      new_metadata_update_authority,
+      master_mint,
+      token_program,
+      token_vault_program_info,
+      edition,
+  )
+  .unwrap()
+}
+
+/// creates a mint_edition_proxy instruction printing `edition` of `master_mint` into `new_mint`
+#[allow(clippy::too_many_arguments)]
+pub fn try_mint_edition_from_master_edition_via_vault_proxy_for_mint(
+  program_id: Pubkey,
+  new_mint: Pubkey,
+  new_mint_authority: Pubkey,
+  payer: Pubkey,
+  vault_authority: Pubkey,
+  safety_deposit_store: Pubkey,
+  safety_deposit_box: Pubkey,
+  vault: Pubkey,
+  new_metadata_update_authority: Pubkey,
+  master_mint: Pubkey,
+  token_program: Pubkey,
+  token_vault_program_info: Pubkey,
+  edition: u64,
+) -> Result<Instruction, MetadataBuildError> {
+  let (new_metadata, _) = pda::find_metadata_account(&program_id, &new_mint);
+  let (new_edition, _) = pda::find_master_edition_account(&program_id, &new_mint);
+  let (master_edition, _) = pda::find_master_edition_account(&program_id, &master_mint);
+  let (edition_mark_pda, _) = pda::find_edition_marker_account(&program_id, &master_mint, edition);
+  let (metadata, _) = pda::find_metadata_account(&program_id, &master_mint);
+  try_mint_edition_from_master_edition_via_vault_proxy(
+      program_id,
+      new_metadata,
+      new_edition,
+      master_edition,
+      new_mint,
+      edition_mark_pda,
+      new_mint_authority,
+      payer,
+      vault_authority,
+      safety_deposit_store,
+      safety_deposit_box,
+      vault,
+      new_metadata_update_authority,
+      metadata,
+      token_program,
+      token_vault_program_info,
+      edition,
+  )
+}
+
+/// Program derived addresses of the metadata program
+pub mod pda {
+  use {
+      crate::state::{EDITION, EDITION_MARKER_BIT_SIZE, PREFIX},
+      solana_program::pubkey::Pubkey,
+  };
+
+  /// Metadata account of a mint: `['metadata', program id, mint id]`
+  pub fn find_metadata_account(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+      Pubkey::find_program_address(&[PREFIX.as_bytes(), program_id.as_ref(), mint.as_ref()], program_id)
+  }
+
+  /// Master edition (or edition) account of a mint: `['metadata', program id, mint id, 'edition']`
+  pub fn find_master_edition_account(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+      Pubkey::find_program_address(
+          &[PREFIX.as_bytes(), program_id.as_ref(), mint.as_ref(), EDITION.as_bytes()],
+          program_id,
       )
-      .try_to_vec()
-      .unwrap(),
   }
-}
\ No newline at end of file
+
+  /// Edition marker account holding the bit of `edition`:
+  /// `['metadata', program id, master mint id, 'edition', edition_number]`
+  /// where edition_number = floor(edition/EDITION_MARKER_BIT_SIZE) as a string.
+  pub fn find_edition_marker_account(program_id: &Pubkey, master_mint: &Pubkey, edition: u64) -> (Pubkey, u8) {
+      let edition_number = (edition / EDITION_MARKER_BIT_SIZE).to_string();
+      Pubkey::find_program_address(
+          &[
+              PREFIX.as_bytes(),
+              program_id.as_ref(),
+              master_mint.as_ref(),
+              EDITION.as_bytes(),
+              edition_number.as_bytes(),
+          ],
+          program_id,
+      )
+  }
+
+  /// Byte index and bit mask of `edition` inside the ledger of its edition marker,
+  /// the first edition of a marker is the highest bit of the first byte.
+  pub fn edition_marker_bit(edition: u64) -> (usize, u8) {
+      let offset = edition % EDITION_MARKER_BIT_SIZE;
+      ((offset / 8) as usize, 1 << (7 - offset % 8))
+  }
+
+  #[cfg(test)]
+  mod test {
+      use {super::*, solana_program::pubkey::Pubkey};
+
+      #[test]
+      fn test_edition_marker() {
+          let program_id = Pubkey::new_unique();
+          let mint = Pubkey::new_unique();
+          assert_eq!(edition_marker_bit(0), (0, 0b1000_0000));
+          assert_eq!(edition_marker_bit(9), (1, 0b0100_0000));
+          assert_eq!(edition_marker_bit(247), (30, 0b0000_0001));
+          assert_eq!(edition_marker_bit(248), (0, 0b1000_0000));
+          assert_eq!(
+              find_edition_marker_account(&program_id, &mint, 1),
+              find_edition_marker_account(&program_id, &mint, 247)
+          );
+          assert_ne!(
+              find_edition_marker_account(&program_id, &mint, 247),
+              find_edition_marker_account(&program_id, &mint, 248)
+          );
+          assert_ne!(find_metadata_account(&program_id, &mint), find_master_edition_account(&program_id, &mint));
+      }
+
+      #[test]
+      fn test_for_mint() {
+          let program_id = Pubkey::new_unique();
+          let [new_mint, master_mint, authority] = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+          let instruction = crate::instruction::mint_new_edition_from_master_edition_via_token_for_mint(
+              program_id,
+              new_mint,
+              authority,
+              authority,
+              authority,
+              Pubkey::new_unique(),
+              authority,
+              master_mint,
+              300,
+          );
+          let pubkeys: Vec<Pubkey> = instruction.accounts.iter().map(|account| account.pubkey).collect();
+          assert_eq!(pubkeys[0], find_metadata_account(&program_id, &new_mint).0);
+          assert_eq!(pubkeys[1], find_master_edition_account(&program_id, &new_mint).0);
+          assert_eq!(pubkeys[2], find_master_edition_account(&program_id, &master_mint).0);
+          assert_eq!(pubkeys[4], find_edition_marker_account(&program_id, &master_mint, 300).0);
+          assert_eq!(pubkeys[10], find_metadata_account(&program_id, &master_mint).0);
+      }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use {super::*, program_instruction::test_utils::check_vectors, solana_program::pubkey::Pubkey};
+
+  #[test]
+  fn test_try_builders() {
+      let program_id = Pubkey::new_unique();
+      let [metadata, creator] = [Pubkey::new_unique(), Pubkey::new_unique()];
+      assert_eq!(
+          try_sign_metadata(program_id, metadata, creator).unwrap(),
+          sign_metadata(program_id, metadata, creator)
+      );
+      let mint = Pubkey::new_unique();
+      let instruction = try_create_master_edition_for_mint(program_id, mint, creator, creator, creator, Some(1)).unwrap();
+      assert_eq!(instruction.accounts[0].pubkey, pda::find_master_edition_account(&program_id, &mint).0);
+      assert_eq!(instruction.data, vec![10, 1, 1, 0, 0, 0, 0, 0, 0, 0]);
+  }
+
+  #[test]
+  fn test_validate_data() {
+      let creator = |address, share| Creator {
+          address,
+          verified: false,
+          share,
+      };
+      let address = Pubkey::new_unique();
+      let mut data = Data {
+          name: "name".to_string(),
+          symbol: "SYM".to_string(),
+          uri: "https://example.com/0.json".to_string(),
+          seller_fee_basis_points: 500,
+          creators: Some(vec![creator(address, 40), creator(Pubkey::new_unique(), 60)]),
+      };
+      assert_eq!(validate_data(&data), Ok(()));
+
+      data.name = "n".repeat(MAX_NAME_LENGTH + 1);
+      data.symbol = "s".repeat(MAX_SYMBOL_LENGTH + 1);
+      data.uri = "u".repeat(MAX_URI_LENGTH + 1);
+      data.seller_fee_basis_points = 10001;
+      data.creators = Some(vec![creator(address, 30); 6]);
+      assert_eq!(
+          validate_data(&data),
+          Err(vec![
+              DataError::NameTooLong(MAX_NAME_LENGTH + 1),
+              DataError::SymbolTooLong(MAX_SYMBOL_LENGTH + 1),
+              DataError::UriTooLong(MAX_URI_LENGTH + 1),
+              DataError::InvalidBasisPoints(10001),
+              DataError::TooManyCreators(6),
+              DataError::DuplicateCreator(address),
+              DataError::InvalidShareTotal(180),
+          ])
+      );
+
+      data.name = "name".to_string();
+      let program_id = Pubkey::new_unique();
+      let err = try_update_metadata_accounts(program_id, address, address, None, Some(data.clone()), None).unwrap_err();
+      assert!(matches!(err, MetadataBuildError::InvalidData(errors) if errors.len() == 6));
+      // The infallible builders leave the checks to the program
+      let instruction = update_metadata_accounts(program_id, address, address, None, Some(data.clone()), None);
+      assert_eq!(
+          MetadataInstruction::unpack(&instruction.data).unwrap(),
+          MetadataInstruction::UpdateMetadataAccount(UpdateMetadataAccountArgs {
+              data: Some(data),
+              update_authority: None,
+              primary_sale_happened: None,
+          })
+      );
+      let err = try_create_metadata_accounts(
+          program_id,
+          address,
+          address,
+          address,
+          address,
+          address,
+          "name".to_string(),
+          "SYM".to_string(),
+          "uri".to_string(),
+          Some(vec![]),
+          0,
+          true,
+          true,
+      )
+      .unwrap_err();
+      assert_eq!(err.to_string(), "invalid metadata: creators must be at least one");
+      let instruction = create_metadata_accounts(
+          program_id,
+          address,
+          address,
+          address,
+          address,
+          address,
+          "name".to_string(),
+          "SYM".to_string(),
+          "uri".to_string(),
+          Some(vec![]),
+          0,
+          true,
+          true,
+      );
+      assert_eq!(instruction.accounts.len(), 7);
+  }
+
+  #[test]
+  #[cfg(not(feature = "deprecated"))]
+  fn test_deprecated_tags() {
+      let instruction = MetadataInstruction::unpack(&[9, 1, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+      assert_eq!(instruction.discriminant(), 9);
+      assert_eq!(instruction.variant_name(), "deprecated instruction 9");
+      assert_eq!(instruction.expected_account_count(), AccountCount::exactly(7));
+      assert_eq!(instruction.pack().unwrap(), vec![9, 1, 0, 0, 0, 0, 0, 0, 0]);
+      assert!(MetadataInstruction::unpack(&[15]).is_err());
+      assert!(MetadataInstruction::Deprecated { tag: 0, data: vec![] }.pack().is_err());
+
+      let data = MetadataInstruction::CreateMasterEdition(CreateMasterEditionArgs { max_supply: None })
+          .pack()
+          .unwrap();
+      assert_eq!(data, vec![10, 0]);
+      assert_eq!(MetadataInstruction::unpack(&data).unwrap().variant_name(), "CreateMasterEdition");
+  }
+
+  #[test]
+  #[cfg(feature = "deprecated")]
+  fn test_deprecated_tags() {
+      use crate::deprecated_instruction::MintPrintingTokensViaTokenArgs;
+
+      let instruction = MetadataInstruction::DeprecatedMintPrintingTokens(MintPrintingTokensViaTokenArgs { supply: 1 });
+      let data = instruction.pack().unwrap();
+      assert_eq!(data, vec![9, 1, 0, 0, 0, 0, 0, 0, 0]);
+      assert_eq!(MetadataInstruction::unpack(&data).unwrap().variant_name(), "DeprecatedMintPrintingTokens");
+  }
+
+  #[test]
+  fn test_serde() {
+      let creator = Creator {
+          address: Pubkey::new_unique(),
+          verified: true,
+          share: 100,
+      };
+      let data = Data {
+          name: "name".to_string(),
+          symbol: "SYM".to_string(),
+          uri: "uri".to_string(),
+          seller_fee_basis_points: 500,
+          creators: Some(vec![creator]),
+      };
+      let create = MetadataInstruction::CreateMetadataAccount(CreateMetadataAccountArgs {
+          data: data.clone(),
+          is_mutable: true,
+      });
+      let json = serde_json::to_value(&create).unwrap();
+      assert_eq!(json["instruction"], "CreateMetadataAccount");
+      assert_eq!(json["args"]["data"]["creators"][0]["share"], 100);
+      assert_eq!(serde_json::from_value::<MetadataInstruction>(json).unwrap(), create);
+
+      let update = MetadataInstruction::UpdateMetadataAccount(UpdateMetadataAccountArgs {
+          data: Some(data),
+          update_authority: None,
+          primary_sale_happened: Some(true),
+      });
+      let json = serde_json::to_string(&update).unwrap();
+      assert_eq!(serde_json::from_str::<MetadataInstruction>(&json).unwrap(), update);
+      assert_eq!(
+          serde_json::to_string(&MetadataInstruction::PuffMetadata).unwrap(),
+          r#"{"instruction":"PuffMetadata"}"#
+      );
+      #[cfg(feature = "deprecated")]
+      {
+          use crate::deprecated_instruction::MintPrintingTokensViaTokenArgs;
+
+          let printing = MetadataInstruction::DeprecatedMintPrintingTokens(MintPrintingTokensViaTokenArgs { supply: 3 });
+          let json = serde_json::to_string(&printing).unwrap();
+          assert_eq!(json, r#"{"instruction":"DeprecatedMintPrintingTokens","args":{"supply":3}}"#);
+          assert_eq!(serde_json::from_str::<MetadataInstruction>(&json).unwrap(), printing);
+      }
+  }
+
+  /// Decodes and encodes every vector of metaplex-token-metadata.rs_vectors.json, any wire
+  /// format change fails. Without the `deprecated` feature the deprecated vectors must decode
+  /// to `Deprecated { tag, data }` and pack back unchanged.
+  #[test]
+  fn test_vectors() {
+      let mut vectors = serde_json::Deserializer::from_str(include_str!("metaplex-token-metadata.rs_vectors.json"));
+      check_vectors(&mut vectors, |instruction: &MetadataInstruction, _| instruction.pack().unwrap());
+  }
+}
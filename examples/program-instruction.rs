@@ -0,0 +1,684 @@
+//! Interface shared by the instruction enums of the bundled programs
+
+use serde::{Deserialize, Serialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::{
+    convert::TryInto,
+    mem::size_of,
+    num::{NonZeroU16, NonZeroU64},
+    ops::Range,
+};
+
+/// Number of accounts an instruction expects. `max` is `None` when the
+/// list is open ended, like the per-asset accounts of a pool.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccountCount {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl AccountCount {
+    pub const fn exactly(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub const fn between(min: usize, max: usize) -> Self {
+        Self { min, max: Some(max) }
+    }
+
+    pub const fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn contains(&self, count: usize) -> bool {
+        count >= self.min
+            && match self.max {
+                Some(max) => count <= max,
+                None => true,
+            }
+    }
+}
+
+/// Instruction enum of a program, with a single pack/unpack signature so one
+/// dispatcher or logger can handle any of them.
+pub trait ProgramInstruction: Sized {
+    /// Leading byte of the encoded instruction data
+    fn discriminant(&self) -> u8;
+
+    /// Name of the enum variant
+    fn variant_name(&self) -> &'static str;
+
+    /// Encodes the instruction data
+    fn pack(&self) -> Result<Vec<u8>, ProgramError>;
+
+    /// Decodes instruction data
+    fn unpack(input: &[u8]) -> Result<Self, ProgramError>;
+
+    /// Number of accounts the instruction expects
+    fn expected_account_count(&self) -> AccountCount;
+
+    /// Description of every instruction of the program
+    fn abi() -> ProgramAbi;
+}
+
+/// Fixed-size little-endian encoding of an instruction field, implemented by
+/// the value types themselves or by a marker type for foreign enums.
+pub trait FieldCodec {
+    type Value;
+    /// Number of encoded bytes
+    const SIZE: usize;
+    /// Type of the field in the [FieldAbi](struct.FieldAbi.html)
+    const ABI_TYPE: &'static str;
+
+    /// Decodes exactly `SIZE` bytes, `None` when they don't hold a valid value
+    fn decode(bytes: &[u8]) -> Option<Self::Value>;
+
+    fn encode(value: &Self::Value, buf: &mut Vec<u8>);
+}
+
+macro_rules! int_codec {
+    ($($ty:ty),*) => {
+        $(impl FieldCodec for $ty {
+            type Value = $ty;
+            const SIZE: usize = size_of::<$ty>();
+            const ABI_TYPE: &'static str = stringify!($ty);
+
+            fn decode(bytes: &[u8]) -> Option<$ty> {
+                bytes.try_into().ok().map(<$ty>::from_le_bytes)
+            }
+
+            fn encode(value: &$ty, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        })*
+    };
+}
+
+int_codec!(u8, u16, u32, u64, u128);
+
+macro_rules! non_zero_codec {
+    ($($ty:ty => $int:ty),*) => {
+        $(impl FieldCodec for $ty {
+            type Value = $ty;
+            const SIZE: usize = size_of::<$int>();
+            const ABI_TYPE: &'static str = stringify!($int);
+
+            fn decode(bytes: &[u8]) -> Option<$ty> {
+                <$int>::decode(bytes).and_then(<$ty>::new)
+            }
+
+            fn encode(value: &$ty, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&value.get().to_le_bytes());
+            }
+        })*
+    };
+}
+
+non_zero_codec!(NonZeroU16 => u16, NonZeroU64 => u64);
+
+impl FieldCodec for [u8; 32] {
+    type Value = [u8; 32];
+    const SIZE: usize = 32;
+    const ABI_TYPE: &'static str = "u8;32";
+
+    fn decode(bytes: &[u8]) -> Option<[u8; 32]> {
+        bytes.try_into().ok()
+    }
+
+    fn encode(value: &[u8; 32], buf: &mut Vec<u8>) {
+        buf.extend_from_slice(value);
+    }
+}
+
+impl FieldCodec for Pubkey {
+    type Value = Pubkey;
+    const SIZE: usize = 32;
+    const ABI_TYPE: &'static str = "Pubkey";
+
+    fn decode(bytes: &[u8]) -> Option<Pubkey> {
+        <[u8; 32]>::decode(bytes).map(Pubkey::new_from_array)
+    }
+
+    fn encode(value: &Pubkey, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(value.as_ref());
+    }
+}
+
+/// Name, encoded size and ABI type of a field of an [InstructionLayout](struct.InstructionLayout.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub size: usize,
+    pub ty: &'static str,
+}
+
+/// Byte layout of an instruction: the tag, then `fields` back to back. When `tail`
+/// is set a variable-length part follows, only the offsets of `fields` are fixed then.
+/// Offsets count from the start of the instruction data, tag included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InstructionLayout {
+    pub tag: u8,
+    pub name: &'static str,
+    pub fields: &'static [FieldSpec],
+    pub tail: bool,
+}
+
+impl InstructionLayout {
+    /// Layout of `tag` in `layouts`, for consts. Panics when there is none, which
+    /// fails the build when evaluated in a const.
+    pub const fn find(layouts: &'static [InstructionLayout], tag: u8) -> &'static InstructionLayout {
+        let mut index = 0;
+        while index < layouts.len() {
+            if layouts[index].tag == tag {
+                return &layouts[index];
+            }
+            index += 1;
+        }
+        panic!("no layout for this tag")
+    }
+
+    /// Encoded size of the tag and the fixed fields
+    pub const fn size(&self) -> usize {
+        let mut size = 1;
+        let mut index = 0;
+        while index < self.fields.len() {
+            size += self.fields[index].size;
+            index += 1;
+        }
+        size
+    }
+
+    /// Byte range of a fixed field, for consts. Panics when the layout has no such
+    /// field, which fails the build when evaluated in a const.
+    pub const fn field_range(&self, field: &str) -> Range<usize> {
+        let mut start = 1;
+        let mut index = 0;
+        while index < self.fields.len() {
+            let spec = &self.fields[index];
+            if str_eq(spec.name, field) {
+                return start..start + spec.size;
+            }
+            start += spec.size;
+            index += 1;
+        }
+        panic!("no field of this name in the layout")
+    }
+
+    /// The fixed fields as ABI inputs, the fields of a tail are left to the caller
+    pub fn abi_inputs(&self) -> Vec<FieldAbi> {
+        self.fields
+            .iter()
+            .map(|field| FieldAbi::new(field.name, field.ty))
+            .collect()
+    }
+
+    /// Every fixed field with its byte range
+    pub fn ranges(&self) -> Vec<(&'static str, Range<usize>)> {
+        let mut offset = 1;
+        self.fields
+            .iter()
+            .map(|field| {
+                offset += field.size;
+                (field.name, offset - field.size..offset)
+            })
+            .collect()
+    }
+
+    /// Byte range of a fixed field
+    pub fn range(&self, field: &str) -> Option<Range<usize>> {
+        self.ranges()
+            .into_iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, range)| range)
+    }
+
+    /// Offset of a fixed field
+    pub fn offset(&self, field: &str) -> Option<usize> {
+        self.range(field).map(|range| range.start)
+    }
+
+    /// Offset and encoded bytes of `value`, as used by a memcmp filter matching the field
+    pub fn memcmp<C: FieldCodec>(&self, field: &str, value: &C::Value) -> Option<(usize, Vec<u8>)> {
+        let offset = self.offset(field)?;
+        let mut bytes = Vec::with_capacity(C::SIZE);
+        C::encode(value, &mut bytes);
+        Some((offset, bytes))
+    }
+
+    /// Decodes a fixed field of `input`, panics when the layout has no such field
+    pub fn read<C: FieldCodec>(&self, input: &[u8], field: &'static str) -> Result<C::Value, LayoutError> {
+        let range = self
+            .range(field)
+            .unwrap_or_else(|| panic!("no field {} in the {} layout", field, self.name));
+        let bytes = input.get(range.clone()).ok_or_else(|| LayoutError::Truncated {
+            field,
+            range: range.clone(),
+        })?;
+        C::decode(bytes).ok_or(LayoutError::Invalid { field, range })
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut index = 0;
+    while index < a.len() {
+        if a[index] != b[index] {
+            return false;
+        }
+        index += 1;
+    }
+    true
+}
+
+/// Reason why a field of an [InstructionLayout](struct.InstructionLayout.html) could not be decoded
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayoutError {
+    /// The input ends before the end of the field
+    Truncated { field: &'static str, range: Range<usize> },
+    /// The bytes of the field don't hold a valid value
+    Invalid { field: &'static str, range: Range<usize> },
+}
+
+/// Declares the byte layout of the variants of an instruction enum and generates from it:
+///
+/// * `LAYOUTS`, the [InstructionLayout](struct.InstructionLayout.html) of every listed tag
+/// * `layout(tag)`, looking one up
+/// * `unpack_fixed(input)`, decoding the `fixed` variants, `Ok(None)` for other tags
+/// * `pack_fixed(&self, buf)`, encoding the `fixed` variants, `false` for the others
+///
+/// Fields are given with their [FieldCodec](trait.FieldCodec.html). The `head` entries
+/// only describe the fixed fields in front of a variable-length tail, their variants are
+/// packed and unpacked by hand.
+///
+/// ```ignore
+/// instruction_layouts! {
+///     impl AmmInstruction {
+///         fixed {
+///             7 => WithdrawPnl {},
+///             9 => Swap(SwapInstruction) { amount_in: u64, minimum_amount_out: u64 },
+///         }
+///         head {
+///             6 => SetParams { param: u8 },
+///         }
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! instruction_layouts {
+    (
+        impl $enum:ident {
+            fixed {
+                $($tag:literal => $variant:ident $(($args:ident))? { $($field:ident: $codec:ty),* $(,)? }),* $(,)?
+            }
+            head {
+                $($head_tag:literal => $head_variant:ident { $($head_field:ident: $head_codec:ty),* $(,)? }),* $(,)?
+            }
+        }
+    ) => {
+        impl $enum {
+            /// Byte layout of every tag
+            pub const LAYOUTS: &'static [$crate::InstructionLayout] = &[
+                $($crate::InstructionLayout {
+                    tag: $tag,
+                    name: stringify!($variant),
+                    fields: &[$($crate::FieldSpec {
+                        name: stringify!($field),
+                        size: <$codec as $crate::FieldCodec>::SIZE,
+                        ty: <$codec as $crate::FieldCodec>::ABI_TYPE,
+                    }),*],
+                    tail: false,
+                },)*
+                $($crate::InstructionLayout {
+                    tag: $head_tag,
+                    name: stringify!($head_variant),
+                    fields: &[$($crate::FieldSpec {
+                        name: stringify!($head_field),
+                        size: <$head_codec as $crate::FieldCodec>::SIZE,
+                        ty: <$head_codec as $crate::FieldCodec>::ABI_TYPE,
+                    }),*],
+                    tail: true,
+                },)*
+            ];
+
+            /// Byte layout of a tag
+            pub fn layout(tag: u8) -> Option<&'static $crate::InstructionLayout> {
+                Self::LAYOUTS.iter().find(|layout| layout.tag == tag)
+            }
+
+            /// Decodes the variants with a fixed layout, trailing bytes are ignored
+            #[allow(unused_variables)]
+            fn unpack_fixed(input: &[u8]) -> Result<Option<Self>, $crate::LayoutError> {
+                let tag = match input.first() {
+                    Some(tag) => *tag,
+                    None => return Ok(None),
+                };
+                let layout = match Self::layout(tag) {
+                    Some(layout) if !layout.tail => layout,
+                    _ => return Ok(None),
+                };
+                Ok(Some(match tag {
+                    $($tag => {
+                        $(let $field = layout.read::<$codec>(input, stringify!($field))?;)*
+                        $crate::instruction_layouts!(@value $variant ($($args)?) { $($field),* })
+                    })*
+                    _ => return Ok(None),
+                }))
+            }
+
+            /// Encodes the variants with a fixed layout, returns false for the others
+            #[allow(unreachable_patterns)]
+            fn pack_fixed(&self, buf: &mut Vec<u8>) -> bool {
+                match self {
+                    $($crate::instruction_layouts!(@value $variant ($($args)?) { $($field),* }) => {
+                        buf.push($tag);
+                        $(<$codec as $crate::FieldCodec>::encode($field, buf);)*
+                    })*
+                    _ => return false,
+                }
+                true
+            }
+        }
+    };
+    (@value $variant:ident () { $($field:ident),* }) => {
+        Self::$variant { $($field),* }
+    };
+    (@value $variant:ident ($args:ident) { $($field:ident),* }) => {
+        Self::$variant($args { $($field),* })
+    };
+}
+
+/// Self-description of the instructions of a program, serialized to the
+/// `{instructions: [{code, name, inputs, accounts}]}` shape written by solabi.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProgramAbi {
+    pub instructions: Vec<InstructionAbi>,
+    /// Structs and enums referenced by the inputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<TypeAbi>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstructionAbi {
+    pub code: u8,
+    pub name: String,
+    /// Fields in wire order
+    pub inputs: Vec<FieldAbi>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs_name: Option<String>,
+    pub accounts: Vec<AccountAbi>,
+    /// Name of the builder function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op_name: Option<String>,
+}
+
+impl InstructionAbi {
+    pub fn new(code: u8, name: &str, inputs: Vec<FieldAbi>, accounts: Vec<AccountAbi>) -> Self {
+        Self {
+            code,
+            name: name.to_string(),
+            inputs,
+            inputs_name: None,
+            accounts,
+            op_name: None,
+        }
+    }
+
+    pub fn inputs_name(mut self, inputs_name: &str) -> Self {
+        self.inputs_name = Some(inputs_name.to_string());
+        self
+    }
+
+    pub fn op_name(mut self, op_name: &str) -> Self {
+        self.op_name = Some(op_name.to_string());
+        self
+    }
+}
+
+/// A field of instruction data. Types are written as:
+///
+///   * `u8` to `u128`, `i8` to `i128`, `bool`, `Pubkey` and `String`
+///   * `T;N` a fixed array of N elements, e.g. `u8;32`
+///   * `T;field` an array whose length is held by a previous field
+///   * `T;..` elements up to the end of the instruction data
+///   * `Option<T>` and `Vec<T>` with Borsh encoding
+///   * the name of an entry of `types`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldAbi {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Only encoded when the condition holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+}
+
+impl FieldAbi {
+    pub fn new(name: &str, ty: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ty: ty.to_string(),
+            when: None,
+        }
+    }
+
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.when = Some(condition);
+        self
+    }
+}
+
+/// Presence condition of a field, on the value of a previous integer field.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub field: String,
+    #[serde(rename = "in", default, skip_serializing_if = "Option::is_none")]
+    pub is_in: Option<Vec<u64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_in: Option<Vec<u64>>,
+}
+
+impl Condition {
+    pub fn is_in(field: &str, values: Vec<u64>) -> Self {
+        Self {
+            field: field.to_string(),
+            is_in: Some(values),
+            not_in: None,
+        }
+    }
+
+    pub fn not_in(field: &str, values: Vec<u64>) -> Self {
+        Self {
+            field: field.to_string(),
+            is_in: None,
+            not_in: Some(values),
+        }
+    }
+
+    pub fn holds(&self, value: u64) -> bool {
+        let is_in = match &self.is_in {
+            Some(values) => values.contains(&value),
+            None => true,
+        };
+        let not_in = match &self.not_in {
+            Some(values) => !values.contains(&value),
+            None => true,
+        };
+        is_in && not_in
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountAbi {
+    pub is_account: bool,
+    pub is_readonly: bool,
+    pub name: String,
+    pub is_signer: bool,
+    /// The account may be left out, it is always last or followed by optional accounts
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_optional: bool,
+    /// Any number of accounts, split evenly between the repeated entries of an instruction
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_repeated: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl AccountAbi {
+    pub fn new(name: &str, is_writable: bool, is_signer: bool) -> Self {
+        Self {
+            is_account: true,
+            is_readonly: !is_writable,
+            name: name.to_string(),
+            is_signer,
+            is_optional: false,
+            is_repeated: false,
+        }
+    }
+
+    pub fn readonly(name: &str) -> Self {
+        Self::new(name, false, false)
+    }
+
+    pub fn writable(name: &str) -> Self {
+        Self::new(name, true, false)
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.is_optional = true;
+        self
+    }
+
+    pub fn repeated(mut self) -> Self {
+        self.is_repeated = true;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeAbi {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: TypeKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TypeKind {
+    Struct { fields: Vec<FieldAbi> },
+    /// Unit variants encoded as a u8 of their position
+    Enum { variants: Vec<String> },
+}
+
+impl TypeAbi {
+    pub fn new_struct(name: &str, fields: Vec<FieldAbi>) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: TypeKind::Struct { fields },
+        }
+    }
+
+    pub fn new_enum(name: &str, variants: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            kind: TypeKind::Enum {
+                variants: variants.iter().map(|variant| variant.to_string()).collect(),
+            },
+        }
+    }
+}
+
+/// Golden vector checks shared by the tests of the bundled programs, enabled as a
+/// dev-dependency with the `test-utils` feature.
+#[cfg(feature = "test-utils")]
+pub mod test_utils {
+    use super::ProgramInstruction;
+    use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+    use std::{collections::BTreeSet, fmt::Debug};
+
+    /// Entry of a `*_vectors.json` file. `instruction` is `None` when the variant is
+    /// behind a cargo feature that is off, `layout` names the encoding of the
+    /// programs with more than one.
+    #[derive(Deserialize)]
+    struct TestVector<T> {
+        name: String,
+        #[serde(deserialize_with = "from_hex")]
+        data: Vec<u8>,
+        instruction: VectorInstruction<T>,
+        #[serde(default)]
+        feature: Option<String>,
+        #[serde(default)]
+        layout: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum VectorInstruction<T> {
+        Known(T),
+        Unknown(serde::de::IgnoredAny),
+    }
+
+    fn from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        if hex.len() % 2 == 1 {
+            return Err(serde::de::Error::custom("odd number of hex digits"));
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2).unwrap_or(""), 16))
+            .collect::<Result<_, _>>()
+            .map_err(serde::de::Error::custom)
+    }
+
+    /// Decodes and encodes every golden vector read from `deserializer`, panicking on
+    /// the first wire format change, and checks every instruction of the ABI has one.
+    /// `pack` encodes the expected instruction with the layout of the vector. The
+    /// vectors of a disabled feature only have to unpack and pack back unchanged.
+    /// Returns the decoded instructions for the checks specific to a program.
+    pub fn check_vectors<'de, T, D>(deserializer: D, pack: impl Fn(&T, Option<&str>) -> Vec<u8>) -> Vec<T>
+    where
+        T: ProgramInstruction + DeserializeOwned + PartialEq + Debug,
+        D: Deserializer<'de>,
+    {
+        let vectors = Vec::<TestVector<T>>::deserialize(deserializer).expect("invalid vectors");
+        let mut codes = BTreeSet::new();
+        let mut instructions = Vec::new();
+        for vector in vectors {
+            let name = vector.name.as_str();
+            let code = *vector.data.first().expect("empty data");
+            codes.insert(code);
+            match vector.instruction {
+                VectorInstruction::Known(expected) => {
+                    assert_eq!(T::unpack(&vector.data).as_ref(), Ok(&expected), "unpack {}", name);
+                    assert_eq!(pack(&expected, vector.layout.as_deref()), vector.data, "pack {}", name);
+                    instructions.push(expected);
+                }
+                VectorInstruction::Unknown(_) => {
+                    assert!(vector.feature.is_some(), "unpack {}: the instruction doesn't deserialize", name);
+                    let instruction = T::unpack(&vector.data).unwrap();
+                    assert_eq!(instruction.discriminant(), code, "unpack {}", name);
+                    assert_eq!(instruction.pack(), Ok(vector.data), "pack {}", name);
+                }
+            }
+        }
+        for instruction in T::abi().instructions {
+            assert!(codes.contains(&instruction.code), "no vector for {}", instruction.name);
+        }
+        instructions
+    }
+}
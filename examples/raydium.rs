@@ -5,18 +5,28 @@
 use crate::error::AmmError;
 use crate::state::{Fees, AmmParams};
 use solana_program::{
+    account_info::{next_account_info, AccountInfo},
     instruction::{AccountMeta, Instruction},
+    msg,
     program_error::ProgramError,
     pubkey::Pubkey,
     program_pack::Pack,
     sysvar,
 };
-use std::convert::TryInto;
+use std::collections::HashSet;
+use std::convert::{TryFrom, TryInto};
 use std::mem::size_of;
 use arrayref::{array_ref};
+use program_instruction::{
+    instruction_layouts, AccountAbi, AccountCount, Condition, FieldAbi, InstructionAbi, InstructionLayout, ProgramAbi,
+    ProgramInstruction, TypeAbi,
+};
+#[cfg(any(test, feature = "serde"))]
+use serde::{Deserialize, Serialize};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct InitializeInstruction {
     /// nonce used to create valid program address
     pub nonce: u8,
@@ -24,6 +34,7 @@ pub struct InitializeInstruction {
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct MonitorStepInstruction {
     /// max value of plan/new/cancel orders
     pub plan_order_limit: u16,
@@ -33,6 +44,7 @@ pub struct MonitorStepInstruction {
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct DepositInstruction {
     /// Pool token amount to transfer. token_a and token_b amount are set by
     /// the current exchange rate and size of the pool
@@ -43,6 +55,7 @@ pub struct DepositInstruction {
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct WithdrawInstruction {
     /// Pool token amount to transfer. token_a and token_b amount are set by
     /// the current exchange rate and size of the pool
@@ -51,27 +64,150 @@ pub struct WithdrawInstruction {
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct WithdrawTransferInstruction {
     pub limit: u16,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct SetParamsInstruction {
     pub param: u8,
     pub value: Option<u64>,
     pub new_pubkey: Option<Pubkey>,
+    #[cfg_attr(any(test, feature = "serde"), serde(with = "serde_fees"))]
     pub fees: Option<Fees>,
 }
 
+/// Serde support for `Option<Fees>`, the state types don't implement it.
+#[cfg(any(test, feature = "serde"))]
+mod serde_fees {
+    use crate::state::Fees;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Fees")]
+    struct FeesDef {
+        min_separate_numerator: u64,
+        min_separate_denominator: u64,
+        trade_fee_numerator: u64,
+        trade_fee_denominator: u64,
+        pnl_numerator: u64,
+        pnl_denominator: u64,
+        swap_fee_numerator: u64,
+        swap_fee_denominator: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "FeesDef")] Fees);
+
+    pub fn serialize<S: Serializer>(fees: &Option<Fees>, serializer: S) -> Result<S::Ok, S::Error> {
+        fees.map(Wrapper).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Fees>, D::Error> {
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(fees)| fees))
+    }
+}
+
+/// An [AmmParams](../state/enum.AmmParams.html) set from a u64 value, that is
+/// any of them but `AmmOwner`, `PnlOwner` and `Fees`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueParam(AmmParams);
+
+impl ValueParam {
+    pub fn new(param: AmmParams) -> Option<Self> {
+        match param {
+            AmmParams::AmmOwner | AmmParams::PnlOwner | AmmParams::Fees => None,
+            param => Some(Self(param)),
+        }
+    }
+
+    pub fn param(&self) -> AmmParams {
+        self.0
+    }
+}
+
+/// The [AmmParams](../state/enum.AmmParams.html) of a param byte, `AmmParams::from_u64`
+/// panics past the last one.
+fn amm_params(param: u8) -> Option<AmmParams> {
+    if param as u64 > AmmParams::UpdateOpenOrder as u64 {
+        return None;
+    }
+    Some(AmmParams::from_u64(param as u64))
+}
+
+/// Typed form of [SetParamsInstruction](struct.SetParamsInstruction.html), each variant
+/// carries the payload its param is encoded with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SetParams {
+    AmmOwner(Pubkey),
+    PnlOwner(Pubkey),
+    Fees(Fees),
+    Value(ValueParam, u64),
+}
+
+impl From<SetParams> for SetParamsInstruction {
+    fn from(params: SetParams) -> Self {
+        match params {
+            SetParams::AmmOwner(new_pubkey) => SetParamsInstruction {
+                param: AmmParams::AmmOwner as u8,
+                value: None,
+                new_pubkey: Some(new_pubkey),
+                fees: None,
+            },
+            SetParams::PnlOwner(new_pubkey) => SetParamsInstruction {
+                param: AmmParams::PnlOwner as u8,
+                value: None,
+                new_pubkey: Some(new_pubkey),
+                fees: None,
+            },
+            SetParams::Fees(fees) => SetParamsInstruction {
+                param: AmmParams::Fees as u8,
+                value: None,
+                new_pubkey: None,
+                fees: Some(fees),
+            },
+            SetParams::Value(param, value) => SetParamsInstruction {
+                param: param.param() as u8,
+                value: Some(value),
+                new_pubkey: None,
+                fees: None,
+            },
+        }
+    }
+}
+
+impl TryFrom<SetParamsInstruction> for SetParams {
+    type Error = AmmError;
+
+    /// Fails on the combinations `pack` rejects, payloads other than the one of the param are ignored as by `pack`.
+    fn try_from(instruction: SetParamsInstruction) -> Result<Self, Self::Error> {
+        let SetParamsInstruction { param, value, new_pubkey, fees } = instruction;
+        match (amm_params(param).ok_or(AmmError::InvalidInput)?, value, new_pubkey, fees) {
+            (AmmParams::AmmOwner, _, Some(new_pubkey), _) => Ok(SetParams::AmmOwner(new_pubkey)),
+            (AmmParams::PnlOwner, _, Some(new_pubkey), _) => Ok(SetParams::PnlOwner(new_pubkey)),
+            (AmmParams::Fees, _, _, Some(fees)) => Ok(SetParams::Fees(fees)),
+            (param, Some(value), _, _) => match ValueParam::new(param) {
+                Some(param) => Ok(SetParams::Value(param, value)),
+                None => Err(AmmError::InvalidInput),
+            },
+            _ => Err(AmmError::InvalidInput),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct WithdrawSrmInstruction {
     pub amount: u64,
 }
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize))]
 pub struct SwapInstruction {
     // SOURCE amount to transfer, output to DESTINATION is based on the exchange rate
     pub amount_in: u64,
@@ -80,25 +216,32 @@ pub struct SwapInstruction {
 }
 
 /// Instructions supported by the AmmInfo program.
+///
+/// With the `serde` feature it is represented as `{"instruction": "Swap", "args": {...}}`,
+/// `args` being left out for the variants without arguments.
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "serde"), derive(Serialize, Deserialize), serde(tag = "instruction", content = "args"))]
 pub enum AmmInstruction {
     ///   Initializes a new AmmInfo.
     ///
     ///   0. `[]` Spl Token program id
-    ///   1. `[writable, signer]` New amm Account to create.
-    ///   2. `[]` $authority derived from `create_program_address(&[amm Account])`
-    ///   3. `[]` amm open_orders Account
-    ///   4. `[writable]` pool lp mint address. Must be empty, owned by $authority.
-    ///   5. `[]` coin mint address
-    ///   6. `[]` pc mint address
-    ///   7. `[]` pool_token_coin Account. Must be non zero, owned by $authority.
-    ///   8. `[]` pool_token_pc Account. Must be non zero, owned by $authority.
-    ///   9. '[writable]` withdraw queue Account. To save withdraw dest_coin & dest_pc account with must cancle orders.
-    ///   10. `[writable]` token_dest_lp Account. To deposit the initial pool token supply, user is the owner.
-    ///   11. `[writable]` token_temp_lp Account. To save withdraw lp with must cancle orders as temp to transfer later.
-    ///   12. `[]` serum dex program id
-    ///   13. `[]` serum market Account. serum_dex program is the owner.
+    ///   1. `[]` System program id
+    ///   2. `[]` Rent program id
+    ///   3. `[writable]` New amm Account to create.
+    ///   4. `[]` $authority derived from `create_program_address(&[amm Account])`
+    ///   5. `[]` amm open_orders Account
+    ///   6. `[writable]` pool lp mint address. Must be empty, owned by $authority.
+    ///   7. `[]` coin mint address
+    ///   8. `[]` pc mint address
+    ///   9. `[]` pool_token_coin Account. Must be non zero, owned by $authority.
+    ///   10. `[]` pool_token_pc Account. Must be non zero, owned by $authority.
+    ///   11. `[writable]` withdraw queue Account. To save withdraw dest_coin & dest_pc account with must cancle orders.
+    ///   12. `[writable]` token_dest_lp Account. To deposit the initial pool token supply, user is the owner.
+    ///   13. `[]` token_temp_lp Account. To save withdraw lp with must cancle orders as temp to transfer later.
+    ///   14. `[]` serum dex program id
+    ///   15. `[]` serum market Account. serum_dex program is the owner.
+    ///   16. `[writable, signer]` user wallet Account
     Initialize(InitializeInstruction),
 
     ///   Continue Initializes the new AmmInfo.
@@ -119,6 +262,7 @@ pub enum AmmInstruction {
     ///   13. `[writable]` event_q Account
     ///   14. `[writable]` bids Account
     ///   15. `[writable]` asks Account
+    ///   16. `[writable]` (optional) the (M)SRM account used for fee discounts
     Initialize2,
 
     ///   MonitorStep. To monitor state turn around step by step.
@@ -202,6 +346,7 @@ pub enum AmmInstruction {
     ///   11. `[writable]` coin_vault Account
     ///   12. `[writable]` pc_vault Account
     ///   13. '[]` vault_signer Account
+    ///   14..N. `[writable]` the withdraw dest_coin & dest_pc Accounts saved in the withdraw queue
     WithdrawTransfer(WithdrawTransferInstruction),
 
     ///   Set amm params
@@ -210,6 +355,8 @@ pub enum AmmInstruction {
     ///   1. `[]` $authority derived from `create_program_address(&[amm Account])`
     ///   2. `[singer]` amm Account owner
     ///   3. `[]` (optional) the account to replace owner
+    ///   4. `[]` (optional) amm open_orders Account, always sent with the amm target_orders Account
+    ///   5. `[writable]` (optional) amm target_orders Account
     SetParams(SetParamsInstruction),
 
     ///   Withdraw Pnl from pool
@@ -263,44 +410,103 @@ pub enum AmmInstruction {
     ///   17. `[singer]` user owner Account
     Swap(SwapInstruction),
 
+    ///   Pre initializes the accounts owned by a new AmmInfo.
+    ///
+    ///   0. `[]` Spl Token program id
+    ///   1. `[]` System program id
+    ///   2. `[]` Rent program id
+    ///   3. `[writable]` amm target_orders Account. To store plan orders infomations.
+    ///   4. `[writable]` withdraw queue Account. To save withdraw dest_coin & dest_pc account with must cancle orders.
+    ///   5. `[]` $authority derived from `create_program_address(&[amm Account])`
+    ///   6. `[writable]` pool lp mint address. Must be empty, owned by $authority.
+    ///   7. `[]` coin mint address
+    ///   8. `[]` pc mint address
+    ///   9. `[writable]` pool_token_coin Account. Must be empty, owned by $authority.
+    ///   10. `[writable]` pool_token_pc Account. Must be empty, owned by $authority.
+    ///   11. `[writable]` token_temp_lp Account. To save withdraw lp with must cancle orders as temp to transfer later.
+    ///   12. `[]` serum market Account. serum_dex program is the owner.
+    ///   13. `[writable, signer]` user wallet Account
     PreInitialize(InitializeInstruction),
 }
 
+instruction_layouts! {
+    impl AmmInstruction {
+        fixed {
+            0 => Initialize(InitializeInstruction) { nonce: u8 },
+            1 => Initialize2 {},
+            2 => MonitorStep(MonitorStepInstruction) {
+                plan_order_limit: u16,
+                place_order_limit: u16,
+                cancel_order_limit: u16,
+            },
+            3 => Deposit(DepositInstruction) { max_coin_amount: u64, max_pc_amount: u64, base_side: u64 },
+            4 => Withdraw(WithdrawInstruction) { amount: u64 },
+            5 => WithdrawTransfer(WithdrawTransferInstruction) { limit: u16 },
+            7 => WithdrawPnl {},
+            8 => WithdrawSrm(WithdrawSrmInstruction) { amount: u64 },
+            9 => Swap(SwapInstruction) { amount_in: u64, minimum_amount_out: u64 },
+            10 => PreInitialize(InitializeInstruction) { nonce: u8 },
+        }
+        head {
+            6 => SetParams { param: u8 },
+        }
+    }
+}
+
+/// Reason why [AmmInstruction::unpack_strict](enum.AmmInstruction.html#method.unpack_strict)
+/// rejected instruction data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrictUnpackError {
+    /// The data doesn't decode, with the error of `unpack`
+    Invalid(ProgramError),
+    /// The data goes on for `trailing_len` bytes after the last field
+    TrailingBytes {
+        tag: u8,
+        expected_len: usize,
+        trailing_len: usize,
+    },
+}
+
+impl std::fmt::Display for StrictUnpackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Invalid(err) => write!(f, "Invalid instruction data: {:?}", err),
+            Self::TrailingBytes {
+                tag,
+                expected_len,
+                trailing_len,
+            } => write!(
+                f,
+                "Tag {}: {} bytes after the last field, expected {} bytes",
+                tag, trailing_len, expected_len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StrictUnpackError {}
+
+impl From<StrictUnpackError> for ProgramError {
+    fn from(err: StrictUnpackError) -> Self {
+        match err {
+            StrictUnpackError::Invalid(err) => err,
+            StrictUnpackError::TrailingBytes { .. } => AmmError::InvalidInstruction.into(),
+        }
+    }
+}
+
 impl AmmInstruction {
     /// Unpacks a byte buffer into a [AmmInstruction](enum.AmmInstruction.html).
+    /// The bytes after the last field are ignored, see [unpack_strict](#method.unpack_strict).
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        if let Some(instruction) = Self::unpack_fixed(input).map_err(|_| AmmError::InvalidInstruction)? {
+            return Ok(instruction);
+        }
         let (&tag, rest) = input.split_first().ok_or(AmmError::InvalidInstruction)?;
         Ok(match tag {
-            0 => {
-                let (nonce, _rest) = Self::unpack_u8(rest)?;
-                Self::Initialize(InitializeInstruction{ nonce })
-            }
-            1 => {
-                Self::Initialize2
-            }
-            2 => {
-                let (plan_order_limit, rest) = Self::unpack_u16(rest)?;
-                let (place_order_limit, rest) = Self::unpack_u16(rest)?;
-                let (cancel_order_limit, _rest) = Self::unpack_u16(rest)?;
-                Self::MonitorStep(MonitorStepInstruction{plan_order_limit, place_order_limit, cancel_order_limit})
-            }
-            3  => {
-                let (max_coin_amount, rest) = Self::unpack_u64(rest)?;
-                let (max_pc_amount, rest) = Self::unpack_u64(rest)?;
-                let (base_side, _rest) = Self::unpack_u64(rest)?;
-                Self::Deposit(DepositInstruction{ max_coin_amount, max_pc_amount, base_side })
-            }
-            4 => {
-                let (amount, _rest) = Self::unpack_u64(rest)?;
-                Self::Withdraw(WithdrawInstruction{ amount })
-            }
-            5 => {
-                let (limit, _rest) = Self::unpack_u16(rest)?;
-                Self::WithdrawTransfer(WithdrawTransferInstruction{ limit })
-            }
             6 => {
                 let (param, rest) = Self::unpack_u8(rest)?;
-                match AmmParams::from_u64(param as u64) {
+                match amm_params(param).ok_or(AmmError::InvalidInstruction)? {
                     AmmParams::AmmOwner | AmmParams::PnlOwner => {
                         if rest.len() >= 32 {
                             let new_pubkey = array_ref![rest, 0, 32];
@@ -331,26 +537,34 @@ impl AmmInstruction {
                     }
                 }
             }
-            7 => {
-                Self::WithdrawPnl
-            }
-            8 => {
-                let (amount, _rest) = Self::unpack_u64(rest)?;
-                Self::WithdrawSrm(WithdrawSrmInstruction{ amount })
-            }
-            9 => {
-                let (amount_in, rest) = Self::unpack_u64(rest)?;
-                let (minimum_amount_out, _rest) = Self::unpack_u64(rest)?;
-                Self::Swap(SwapInstruction{amount_in, minimum_amount_out})
-            }
-            10 => {
-                let (nonce, _rest) = Self::unpack_u8(rest)?;
-                Self::PreInitialize(InitializeInstruction{ nonce })
-            }
             _ => return Err(AmmError::InvalidInstruction.into()),
         })
     }
 
+    /// Unpacks a byte buffer like [unpack](#method.unpack) but rejects the data going on
+    /// after the last field, so that it packs back to the same bytes.
+    pub fn unpack_strict(input: &[u8]) -> Result<Self, StrictUnpackError> {
+        let instruction = Self::unpack(input).map_err(StrictUnpackError::Invalid)?;
+        let tag = input[0];
+        let expected_len = match &instruction {
+            Self::SetParams(SetParamsInstruction { new_pubkey: Some(_), .. }) => 2 + 32,
+            Self::SetParams(SetParamsInstruction { fees: Some(_), .. }) => 2 + Fees::LEN,
+            Self::SetParams(_) => 2 + 8,
+            _ => match Self::layout(tag) {
+                Some(layout) => layout.size(),
+                None => input.len(),
+            },
+        };
+        if input.len() > expected_len {
+            return Err(StrictUnpackError::TrailingBytes {
+                tag,
+                expected_len,
+                trailing_len: input.len() - expected_len,
+            });
+        }
+        Ok(instruction)
+    }
+
     fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
         if input.len() >= 1 {
             let (amount, rest) = input.split_at(1);
@@ -365,20 +579,6 @@ impl AmmInstruction {
         }
     }
 
-    fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
-        if input.len() >= 2 {
-            let (amount, rest) = input.split_at(2);
-            let amount = amount
-                .get(..2)
-                .and_then(|slice| slice.try_into().ok())
-                .map(u16::from_le_bytes)
-                .ok_or(AmmError::InvalidInstruction)?;
-            Ok((amount, rest))
-        } else {
-            Err(AmmError::InvalidInstruction.into())
-        }
-    }
-
     fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
         if input.len() >= 8 {
             let (amount, rest) = input.split_at(8);
@@ -397,39 +597,10 @@ impl AmmInstruction {
     pub fn pack(&self) -> Result<Vec<u8>, ProgramError> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match &*self {
-            Self::Initialize(
-                InitializeInstruction { nonce }
-            ) => {
-                buf.push(0);
-                buf.push(*nonce);
-            }
-            Self::Initialize2 => {
-                buf.push(1);
-            }
-            Self::MonitorStep(MonitorStepInstruction{ plan_order_limit, place_order_limit, cancel_order_limit }) => {
-                buf.push(2);
-                buf.extend_from_slice(&plan_order_limit.to_le_bytes());
-                buf.extend_from_slice(&place_order_limit.to_le_bytes());
-                buf.extend_from_slice(&cancel_order_limit.to_le_bytes());
-            }
-            Self::Deposit(DepositInstruction{ max_coin_amount, max_pc_amount, base_side }) => {
-                buf.push(3);
-                buf.extend_from_slice(&max_coin_amount.to_le_bytes());
-                buf.extend_from_slice(&max_pc_amount.to_le_bytes());
-                buf.extend_from_slice(&base_side.to_le_bytes());
-            }
-            Self::Withdraw(WithdrawInstruction{ amount }) => {
-                buf.push(4);
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-            Self::WithdrawTransfer(WithdrawTransferInstruction{ limit }) => {
-                buf.push(5);
-                buf.extend_from_slice(&limit.to_le_bytes());
-            }
             Self::SetParams(SetParamsInstruction{param, value, new_pubkey, fees}) => {
                 buf.push(6);
                 buf.push(*param);
-                match AmmParams::from_u64(*param as u64) {
+                match amm_params(*param).ok_or(AmmError::InvalidInput)? {
                     AmmParams::AmmOwner | AmmParams::PnlOwner => {
                         let new_pubkey = match new_pubkey {
                             Some(a) => a,
@@ -455,23 +626,8 @@ impl AmmInstruction {
                     }
                 }
             }
-            Self::WithdrawPnl => {
-                buf.push(7);
-            }
-            Self::WithdrawSrm(WithdrawSrmInstruction{ amount }) => {
-                buf.push(8);
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-            Self::Swap(SwapInstruction{amount_in, minimum_amount_out}) => {
-                buf.push(9);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
-            }
-            Self::PreInitialize(
-                InitializeInstruction { nonce }
-            ) => {
-                buf.push(10);
-                buf.push(*nonce);
+            _ => {
+                self.pack_fixed(&mut buf);
             }
         }
         Ok(buf)
@@ -686,7 +842,7 @@ pub fn deposit(
     })
 }
 
-/// Creates a 'withdraw' instruction.
+/// Creates a 'withdraw' instruction, [WithdrawBuilder](struct.WithdrawBuilder.html) sets the accounts by name.
 pub fn withdraw(
     program_id: &Pubkey,
     amm_id: &Pubkey,
@@ -704,8 +860,8 @@ pub fn withdraw(
     serum_pc_vault_account: &Pubkey,
     serum_vault_signer: &Pubkey,
     user_lp_token_account: &Pubkey,
-    uer_coin_token_account: &Pubkey,
-    uer_pc_token_account: &Pubkey,
+    user_coin_token_account: &Pubkey,
+    user_pc_token_account: &Pubkey,
     user_owner: &Pubkey,
 
     amount: u64,
@@ -733,8 +889,8 @@ pub fn withdraw(
         AccountMeta::new_readonly(*serum_vault_signer, false),
         // user
         AccountMeta::new(*user_lp_token_account, false),
-        AccountMeta::new(*uer_coin_token_account, false),
-        AccountMeta::new(*uer_pc_token_account, false),
+        AccountMeta::new(*user_coin_token_account, false),
+        AccountMeta::new(*user_pc_token_account, false),
         AccountMeta::new_readonly(*user_owner, true),
     ];
 
@@ -745,7 +901,7 @@ pub fn withdraw(
     })
 }
 
-/// Creates a 'swap' instruction.
+/// Creates a 'swap' instruction, [SwapBuilder](struct.SwapBuilder.html) sets the accounts by name.
 pub fn swap(
     program_id: &Pubkey,
     amm_id: &Pubkey,
@@ -762,8 +918,8 @@ pub fn swap(
     serum_coin_vault_account: &Pubkey,
     serum_pc_vault_account: &Pubkey,
     serum_vault_signer: &Pubkey,
-    uer_source_token_account: &Pubkey,
-    uer_destination_token_account: &Pubkey,
+    user_source_token_account: &Pubkey,
+    user_destination_token_account: &Pubkey,
     user_source_owner: &Pubkey,
 
     amount_in: u64,
@@ -791,8 +947,8 @@ pub fn swap(
         AccountMeta::new(*serum_pc_vault_account, false),
         AccountMeta::new_readonly(*serum_vault_signer, false),
         // user
-        AccountMeta::new(*uer_source_token_account, false),
-        AccountMeta::new(*uer_destination_token_account, false),
+        AccountMeta::new(*user_source_token_account, false),
+        AccountMeta::new(*user_destination_token_account, false),
         AccountMeta::new_readonly(*user_source_owner, true),
     ];
 
@@ -803,7 +959,10 @@ pub fn swap(
     })
 }
 
-/// Creates a 'withdraw_transfer' instruction.
+/// Creates a 'withdraw_transfer' instruction paying out up to `limit` withdraw queue entries.
+/// `withdraw_dest_pks` holds a dest_coin and dest_pc pair per entry, in queue order. The
+/// program pays entry `i` to pair `i`, so at least `limit` pairs are required: with fewer the
+/// transaction would fail on chain, it fails here with `WithdrawLimitTooHigh` instead.
 pub fn withdraw_transfer(
     program_id: &Pubkey,
     amm_id: &Pubkey,
@@ -819,9 +978,18 @@ pub fn withdraw_transfer(
     serum_coin_vault_account: &Pubkey,
     serum_pc_vault_account: &Pubkey,
     serum_vault_signer: &Pubkey,
-    withdraw_dest_pks: &mut Vec<Pubkey>,
+    withdraw_dest_pks: &[Pubkey],
     limit: u16,
-) -> Result<Instruction, ProgramError> {
+) -> Result<Instruction, BuildError> {
+    if withdraw_dest_pks.len() % 2 == 1 {
+        return Err(BuildError::UnpairedWithdrawDestinations(withdraw_dest_pks.len()));
+    }
+    if limit as usize * 2 > withdraw_dest_pks.len() {
+        return Err(BuildError::WithdrawLimitTooHigh {
+            limit,
+            entries: withdraw_dest_pks.len() / 2,
+        });
+    }
     let data = AmmInstruction::WithdrawTransfer(WithdrawTransferInstruction{ limit }).pack()?;
 
     let mut accounts = vec![
@@ -844,9 +1012,8 @@ pub fn withdraw_transfer(
         AccountMeta::new_readonly(*serum_vault_signer, false),
     ];
 
-    for i in 0..withdraw_dest_pks.len() {
-        let dest_coin_or_pc_meta = AccountMeta::new(withdraw_dest_pks[i], false);
-        accounts.push(dest_coin_or_pc_meta);
+    for dest_coin_or_pc in withdraw_dest_pks {
+        accounts.push(AccountMeta::new(*dest_coin_or_pc, false));
     }
 
     Ok(Instruction {
@@ -856,6 +1023,87 @@ pub fn withdraw_transfer(
     })
 }
 
+/// Size limit of a serialized transaction
+pub const PACKET_DATA_SIZE: usize = 1232;
+/// Most accounts a transaction can lock
+pub const MAX_TX_ACCOUNTS: usize = 64;
+
+/// Splits the withdraw queue destinations of `accounts`, a dest_coin and dest_pc pair per entry,
+/// into 'withdraw_transfer' instructions that each fit in a transaction of their own paid by another account.
+/// The instructions are in queue order and the `limit` of each is its number of entries.
+pub fn withdraw_transfer_batches(
+    program_id: &Pubkey,
+    accounts: &WithdrawTransferAccounts,
+) -> Result<Vec<Instruction>, BuildError> {
+    let withdraw_dest_accounts = &accounts.withdraw_dest_accounts;
+    if withdraw_dest_accounts.len() % 2 == 1 {
+        return Err(BuildError::UnpairedWithdrawDestinations(withdraw_dest_accounts.len()));
+    }
+    let pool_accounts = WithdrawTransferAccounts {
+        withdraw_dest_accounts: vec![],
+        ..accounts.clone()
+    };
+    let pool_metas = pool_accounts.to_account_metas();
+    let mut pool_keys: HashSet<Pubkey> = pool_metas.iter().map(|meta| meta.pubkey).collect();
+    pool_keys.insert(*program_id);
+    let data_len = AmmInstruction::WithdrawTransfer(WithdrawTransferInstruction::default()).pack()?.len();
+    // the fee payer is one more key
+    let fits = |keys: &HashSet<Pubkey>, num_accounts: usize| {
+        keys.len() < MAX_TX_ACCOUNTS && transaction_size(keys.len() + 1, num_accounts, data_len) <= PACKET_DATA_SIZE
+    };
+
+    let mut batches = vec![];
+    let mut batch: Vec<Pubkey> = vec![];
+    let mut batch_keys = pool_keys.clone();
+    for entry in withdraw_dest_accounts.chunks(2) {
+        let mut keys = batch_keys.clone();
+        keys.extend(entry);
+        if !batch.is_empty() && !fits(&keys, pool_metas.len() + batch.len() + entry.len()) {
+            batches.push(std::mem::take(&mut batch));
+            keys = pool_keys.clone();
+            keys.extend(entry);
+        }
+        batch.extend(entry);
+        batch_keys = keys;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    batches
+        .into_iter()
+        .map(|withdraw_dest_accounts| {
+            let limit = (withdraw_dest_accounts.len() / 2) as u16;
+            let data = AmmInstruction::WithdrawTransfer(WithdrawTransferInstruction { limit }).pack()?;
+            let accounts = WithdrawTransferAccounts {
+                withdraw_dest_accounts,
+                ..pool_accounts.clone()
+            };
+            Ok(Instruction {
+                program_id: *program_id,
+                accounts: accounts.to_account_metas(),
+                data,
+            })
+        })
+        .collect()
+}
+
+/// Serialized size of a transaction with one signature and one instruction
+fn transaction_size(num_keys: usize, num_accounts: usize, data_len: usize) -> usize {
+    fn compact_len(len: usize) -> usize {
+        match len {
+            0..=0x7f => 1,
+            0x80..=0x3fff => 2,
+            _ => 3,
+        }
+    }
+    // signatures, message header, account keys and recent blockhash
+    let message = 1 + 64 + 3 + compact_len(num_keys) + 32 * num_keys + 32;
+    // program id index, account indexes and data
+    let instruction = 1 + compact_len(num_accounts) + num_accounts + compact_len(data_len) + data_len;
+    message + 1 + instruction
+}
+
 /// Creates a 'withdrawpnl' instruction
 pub fn withdrawpnl(
     program_id: &Pubkey,
@@ -904,20 +1152,20 @@ pub fn withdrawpnl(
     })
 }
 
-/// Creates a 'SetParams' instruction.
+/// Creates a 'SetParams' instruction, the open orders accounts are only
+/// required for `UpdateOpenOrder`.
 pub fn set_params(
     program_id: &Pubkey,
     amm_id: &Pubkey,
     amm_authority: &Pubkey,
     amm_owner_account: &Pubkey,
-    param: u8,
-    value: Option<u64>,
-    new_pubkey: Option<Pubkey>,
-    amm_open_order_account: &Pubkey,
-    amm_target_order_account: &Pubkey,
-    fees: Option<Fees>
+    params: SetParams,
+    amm_open_order_account: Option<&Pubkey>,
+    amm_target_order_account: Option<&Pubkey>,
 ) -> Result<Instruction, ProgramError> {
-    let data = AmmInstruction::SetParams(SetParamsInstruction{param, value, new_pubkey, fees}).pack()?;
+    let instruction: SetParamsInstruction = params.into();
+    let SetParamsInstruction{param, new_pubkey, ..} = instruction;
+    let data = AmmInstruction::SetParams(instruction).pack()?;
 
     let mut accounts = vec![
         AccountMeta::new(*amm_id, false),
@@ -927,9 +1175,14 @@ pub fn set_params(
     if let Some(key) = new_pubkey {
         accounts.push(AccountMeta::new_readonly(key, false))
     }
-    if param == 12 {
-        accounts.push(AccountMeta::new_readonly(*amm_open_order_account, false));
-        accounts.push(AccountMeta::new(*amm_target_order_account, false));
+    if param == AmmParams::UpdateOpenOrder as u8 {
+        match (amm_open_order_account, amm_target_order_account) {
+            (Some(amm_open_order_account), Some(amm_target_order_account)) => {
+                accounts.push(AccountMeta::new_readonly(*amm_open_order_account, false));
+                accounts.push(AccountMeta::new(*amm_target_order_account, false));
+            }
+            _ => return Err(ProgramError::NotEnoughAccountKeys),
+        }
     }
     Ok(Instruction {
         program_id: *program_id,
@@ -1032,6 +1285,1919 @@ pub fn withdrawsrm(
     })
 }
 
+/// Resolves whether a flag is part of the `[writable, signer]` list of an account.
+macro_rules! account_flag {
+    ($want:ident;) => { false };
+    (writable; writable $(, $rest:ident)*) => { true };
+    (signer; signer $(, $rest:ident)*) => { true };
+    ($want:ident; $other:ident $(, $rest:ident)*) => { account_flag!($want; $($rest),*) };
+}
+
+/// Declares the named accounts struct of an [AmmInstruction](enum.AmmInstruction.html) variant.
+/// The flags of each account follow the doc-comment spec on the enum.
+macro_rules! amm_accounts {
+    (
+        $(#[$attr:meta])*
+        $name:ident {
+            $($field:ident: [$($flag:ident),*]),+
+            $(; optional $opt:ident: [$($opt_flag:ident),*])?
+            $(; remaining $rest:ident: [$($rest_flag:ident),*])?
+            $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $name<T = Pubkey> {
+            $(pub $field: T,)+
+            $(pub $opt: Option<T>,)?
+            $(pub $rest: Vec<T>,)?
+        }
+
+        impl<T> $name<T> {
+            /// Number of accounts always expected by the instruction.
+            pub const LEN: usize = [$(stringify!($field)),+].len();
+
+            /// Names and flags of the accounts, as listed in the program ABI.
+            pub fn abi_accounts() -> Vec<AccountAbi> {
+                #[allow(unused_mut)]
+                let mut accounts = vec![
+                    $(AccountAbi::new(
+                        stringify!($field),
+                        account_flag!(writable; $($flag),*),
+                        account_flag!(signer; $($flag),*),
+                    ),)+
+                ];
+                $(accounts.push(AccountAbi::new(
+                    stringify!($opt),
+                    account_flag!(writable; $($opt_flag),*),
+                    account_flag!(signer; $($opt_flag),*),
+                ).optional());)?
+                $(accounts.push(AccountAbi::new(
+                    stringify!($rest),
+                    account_flag!(writable; $($rest_flag),*),
+                    account_flag!(signer; $($rest_flag),*),
+                ).repeated());)?
+                accounts
+            }
+        }
+
+        impl $name<Pubkey> {
+            /// Returns the accounts in the order expected by the program.
+            pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+                #[allow(unused_mut)]
+                let mut accounts = vec![
+                    $(account_meta(
+                        self.$field,
+                        account_flag!(writable; $($flag),*),
+                        account_flag!(signer; $($flag),*),
+                    ),)+
+                ];
+                $(if let Some(key) = self.$opt {
+                    accounts.push(account_meta(
+                        key,
+                        account_flag!(writable; $($opt_flag),*),
+                        account_flag!(signer; $($opt_flag),*),
+                    ));
+                })?
+                $(for key in self.$rest.iter() {
+                    accounts.push(account_meta(
+                        *key,
+                        account_flag!(writable; $($rest_flag),*),
+                        account_flag!(signer; $($rest_flag),*),
+                    ));
+                })?
+                accounts
+            }
+        }
+
+        impl From<&$name<Pubkey>> for Vec<AccountMeta> {
+            fn from(accounts: &$name<Pubkey>) -> Self {
+                accounts.to_account_metas()
+            }
+        }
+
+        impl<'a, 'b> $name<&'a AccountInfo<'b>> {
+            /// Checks the count, signer and writable flags of the accounts passed to the program.
+            pub fn try_from_account_infos(account_infos: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+                check_account_count(account_infos.len(), Self::LEN)?;
+                let account_info_iter = &mut account_infos.iter();
+                $(let $field = check_account_info(
+                    next_account_info(account_info_iter)?,
+                    stringify!($field),
+                    account_flag!(writable; $($flag),*),
+                    account_flag!(signer; $($flag),*),
+                )?;)+
+                $(let $opt = account_info_iter
+                    .next()
+                    .map(|account_info| check_account_info(
+                        account_info,
+                        stringify!($opt),
+                        account_flag!(writable; $($opt_flag),*),
+                        account_flag!(signer; $($opt_flag),*),
+                    ))
+                    .transpose()?;)?
+                $(let $rest = account_info_iter
+                    .map(|account_info| check_account_info(
+                        account_info,
+                        stringify!($rest),
+                        account_flag!(writable; $($rest_flag),*),
+                        account_flag!(signer; $($rest_flag),*),
+                    ))
+                    .collect::<Result<Vec<_>, _>>()?;)?
+                check_no_remaining_accounts(account_info_iter, account_infos.len())?;
+                Ok(Self {
+                    $($field,)+
+                    $($opt,)?
+                    $($rest,)?
+                })
+            }
+        }
+    };
+}
+
+fn account_meta(pubkey: Pubkey, is_writable: bool, is_signer: bool) -> AccountMeta {
+    if is_writable {
+        AccountMeta::new(pubkey, is_signer)
+    } else {
+        AccountMeta::new_readonly(pubkey, is_signer)
+    }
+}
+
+fn check_account_count(len: usize, expected: usize) -> Result<(), ProgramError> {
+    if len < expected {
+        msg!("Expected {} accounts, got {}", expected, len);
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    Ok(())
+}
+
+fn check_no_remaining_accounts<'a, 'b: 'a>(
+    account_info_iter: &mut impl Iterator<Item = &'a AccountInfo<'b>>,
+    len: usize,
+) -> Result<(), ProgramError> {
+    if account_info_iter.next().is_some() {
+        msg!("Too many accounts: {}", len);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+fn check_account_info<'a, 'b>(
+    account_info: &'a AccountInfo<'b>,
+    name: &str,
+    is_writable: bool,
+    is_signer: bool,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    if is_signer && !account_info.is_signer {
+        msg!("{} must be a signer", name);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if is_writable && !account_info.is_writable {
+        msg!("{} must be writable", name);
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(account_info)
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::Initialize](enum.AmmInstruction.html#variant.Initialize).
+    InitializeAccounts {
+        spl_token_program: [],
+        system_program: [],
+        rent_sysvar: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [],
+        lp_mint_address: [writable],
+        coin_mint_address: [],
+        pc_mint_address: [],
+        pool_coin_token_account: [],
+        pool_pc_token_account: [],
+        pool_withdraw_queue: [writable],
+        pool_lp_token_account: [writable],
+        pool_temp_lp_token_account: [],
+        serum_program_id: [],
+        serum_market: [],
+        user_wallet: [writable, signer],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::Initialize2](enum.AmmInstruction.html#variant.Initialize2).
+    Initialize2Accounts {
+        spl_token_program: [],
+        rent_sysvar: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        amm_target_orders: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_req_q: [writable],
+        serum_event_q: [writable],
+        serum_bids: [writable],
+        serum_asks: [writable];
+        optional srm_token_account: [writable]
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::MonitorStep](enum.AmmInstruction.html#variant.MonitorStep).
+    MonitorStepAccounts {
+        spl_token_program: [],
+        rent_sysvar: [],
+        clock_sysvar: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        amm_target_orders: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        pool_withdraw_queue: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_vault_signer: [],
+        serum_req_q: [writable],
+        serum_event_q: [writable],
+        serum_bids: [writable],
+        serum_asks: [writable];
+        optional srm_token_account: [writable]
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::Deposit](enum.AmmInstruction.html#variant.Deposit).
+    DepositAccounts {
+        spl_token_program: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [],
+        amm_target_orders: [writable],
+        lp_mint_address: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        serum_market: [],
+        user_coin_token_account: [writable],
+        user_pc_token_account: [writable],
+        user_lp_token_account: [writable],
+        user_owner: [signer],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::Withdraw](enum.AmmInstruction.html#variant.Withdraw).
+    WithdrawAccounts {
+        spl_token_program: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        amm_target_orders: [writable],
+        lp_mint_address: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        pool_withdraw_queue: [writable],
+        pool_temp_lp_token_account: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_vault_signer: [],
+        user_lp_token_account: [writable],
+        user_coin_token_account: [writable],
+        user_pc_token_account: [writable],
+        user_owner: [signer],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::WithdrawTransfer](enum.AmmInstruction.html#variant.WithdrawTransfer).
+    WithdrawTransferAccounts {
+        spl_token_program: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        lp_mint_address: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        pool_withdraw_queue: [writable],
+        pool_temp_lp_token_account: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_vault_signer: [];
+        remaining withdraw_dest_accounts: [writable]
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::WithdrawPnl](enum.AmmInstruction.html#variant.WithdrawPnl).
+    WithdrawPnlAccounts {
+        spl_token_program: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        coin_pnl_token_account: [writable],
+        pc_pnl_token_account: [writable],
+        pnl_owner_account: [signer],
+        amm_target_orders: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_vault_signer: [],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::WithdrawSrm](enum.AmmInstruction.html#variant.WithdrawSrm).
+    WithdrawSrmAccounts {
+        spl_token_program: [],
+        amm_id: [],
+        amm_owner_account: [signer],
+        amm_authority: [],
+        srm_token: [writable],
+        dest_srm_token: [writable],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::Swap](enum.AmmInstruction.html#variant.Swap).
+    SwapAccounts {
+        spl_token_program: [],
+        amm_id: [writable],
+        amm_authority: [],
+        amm_open_orders: [writable],
+        amm_target_orders: [writable],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        serum_program_id: [],
+        serum_market: [writable],
+        serum_bids: [writable],
+        serum_asks: [writable],
+        serum_event_queue: [writable],
+        serum_coin_vault_account: [writable],
+        serum_pc_vault_account: [writable],
+        serum_vault_signer: [],
+        user_source_token_account: [writable],
+        user_destination_token_account: [writable],
+        user_source_owner: [signer],
+    }
+}
+
+amm_accounts! {
+    /// Accounts expected by [AmmInstruction::PreInitialize](enum.AmmInstruction.html#variant.PreInitialize).
+    PreInitializeAccounts {
+        spl_token_program: [],
+        system_program: [],
+        rent_sysvar: [],
+        amm_target_orders: [writable],
+        pool_withdraw_queue: [writable],
+        amm_authority: [],
+        lp_mint_address: [writable],
+        coin_mint_address: [],
+        pc_mint_address: [],
+        pool_coin_token_account: [writable],
+        pool_pc_token_account: [writable],
+        pool_temp_lp_token_account: [writable],
+        serum_market: [],
+        user_wallet: [writable, signer],
+    }
+}
+
+/// Accounts expected by [AmmInstruction::SetParams](enum.AmmInstruction.html#variant.SetParams).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetParamsAccounts<T = Pubkey> {
+    pub amm_id: T,
+    pub amm_authority: T,
+    pub amm_owner_account: T,
+    /// the account to replace owner
+    pub new_owner: Option<T>,
+    /// open_orders and target_orders are either both sent or both omitted
+    pub amm_open_orders: Option<T>,
+    pub amm_target_orders: Option<T>,
+}
+
+impl<T> SetParamsAccounts<T> {
+    /// Number of accounts always expected by the instruction.
+    pub const LEN: usize = 3;
+
+    /// Number of accounts expected with `params`: the new owner follows `AmmOwner` and
+    /// `PnlOwner`, the open_orders and target_orders pair `UpdateOpenOrder`.
+    pub fn len_with(params: &SetParams) -> usize {
+        match params {
+            SetParams::AmmOwner(_) | SetParams::PnlOwner(_) => Self::LEN + 1,
+            SetParams::Value(param, _) if param.param() == AmmParams::UpdateOpenOrder => Self::LEN + 2,
+            _ => Self::LEN,
+        }
+    }
+
+    /// Names and flags of the accounts, as listed in the program ABI.
+    pub fn abi_accounts() -> Vec<AccountAbi> {
+        vec![
+            AccountAbi::writable("amm_id"),
+            AccountAbi::readonly("amm_authority"),
+            AccountAbi::readonly("amm_owner_account").signer(),
+            AccountAbi::readonly("new_owner").optional(),
+            AccountAbi::readonly("amm_open_orders").optional(),
+            AccountAbi::writable("amm_target_orders").optional(),
+        ]
+    }
+}
+
+impl SetParamsAccounts<Pubkey> {
+    /// Returns the accounts in the order expected by the program.
+    pub fn to_account_metas(&self) -> Vec<AccountMeta> {
+        let mut accounts = vec![
+            AccountMeta::new(self.amm_id, false),
+            AccountMeta::new_readonly(self.amm_authority, false),
+            AccountMeta::new_readonly(self.amm_owner_account, true),
+        ];
+        if let Some(key) = self.new_owner {
+            accounts.push(AccountMeta::new_readonly(key, false));
+        }
+        if let (Some(open_orders), Some(target_orders)) = (self.amm_open_orders, self.amm_target_orders) {
+            accounts.push(AccountMeta::new_readonly(open_orders, false));
+            accounts.push(AccountMeta::new(target_orders, false));
+        }
+        accounts
+    }
+}
+
+impl From<&SetParamsAccounts<Pubkey>> for Vec<AccountMeta> {
+    fn from(accounts: &SetParamsAccounts<Pubkey>) -> Self {
+        accounts.to_account_metas()
+    }
+}
+
+impl<'a, 'b> SetParamsAccounts<&'a AccountInfo<'b>> {
+    /// Checks the count, signer and writable flags of the accounts passed to the program
+    /// with `params`, which decide the optional accounts as in [len_with](#method.len_with).
+    pub fn try_from_account_infos(account_infos: &'a [AccountInfo<'b>], params: &SetParams) -> Result<Self, ProgramError> {
+        let len = Self::len_with(params);
+        check_account_count(account_infos.len(), len)?;
+        let account_info_iter = &mut account_infos.iter();
+        let amm_id = check_account_info(next_account_info(account_info_iter)?, "amm_id", true, false)?;
+        let amm_authority = check_account_info(next_account_info(account_info_iter)?, "amm_authority", false, false)?;
+        let amm_owner_account = check_account_info(next_account_info(account_info_iter)?, "amm_owner_account", false, true)?;
+        let new_owner = if len == Self::LEN + 1 {
+            Some(next_account_info(account_info_iter)?)
+        } else {
+            None
+        };
+        let (amm_open_orders, amm_target_orders) = if len == Self::LEN + 2 {
+            let open_orders = next_account_info(account_info_iter)?;
+            let target_orders = check_account_info(next_account_info(account_info_iter)?, "amm_target_orders", true, false)?;
+            (Some(open_orders), Some(target_orders))
+        } else {
+            (None, None)
+        };
+        check_no_remaining_accounts(account_info_iter, account_infos.len())?;
+        Ok(Self {
+            amm_id,
+            amm_authority,
+            amm_owner_account,
+            new_owner,
+            amm_open_orders,
+            amm_target_orders,
+        })
+    }
+}
+
+/// Reason why an instruction builder could not create its instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BuildError {
+    /// The account with this name was not set
+    MissingAccount(&'static str),
+    /// The instruction data doesn't pack
+    Invalid(ProgramError),
+    /// The withdraw queue destinations are not a dest_coin and dest_pc pair per entry
+    UnpairedWithdrawDestinations(usize),
+    /// `limit` withdraw queue entries would be paid out, destinations are given for `entries`
+    WithdrawLimitTooHigh { limit: u16, entries: usize },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::MissingAccount(name) => write!(f, "Missing account: {}", name),
+            Self::Invalid(err) => write!(f, "Invalid instruction data: {:?}", err),
+            Self::UnpairedWithdrawDestinations(len) => write!(
+                f,
+                "Expected a dest_coin and dest_pc account per withdraw queue entry, got {} accounts",
+                len
+            ),
+            Self::WithdrawLimitTooHigh { limit, entries } => write!(
+                f,
+                "Limit of {} withdraw queue entries, destinations given for {}",
+                limit, entries
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<ProgramError> for BuildError {
+    fn from(err: ProgramError) -> Self {
+        Self::Invalid(err)
+    }
+}
+
+impl From<BuildError> for ProgramError {
+    fn from(err: BuildError) -> Self {
+        match err {
+            BuildError::MissingAccount(_) => ProgramError::NotEnoughAccountKeys,
+            BuildError::Invalid(err) => err,
+            BuildError::UnpairedWithdrawDestinations(_) | BuildError::WithdrawLimitTooHigh { .. } => {
+                AmmError::InvalidInput.into()
+            }
+        }
+    }
+}
+
+/// Declares the builder of a named accounts struct, with a setter per account.
+/// The accounts given a default are filled in when not set, the others are required.
+macro_rules! amm_builder {
+    (
+        $(#[$attr:meta])*
+        $builder:ident => $accounts:ident {
+            $($field:ident $(= $default:expr)?),+
+            $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct $builder {
+            program_id: Pubkey,
+            $($field: Option<Pubkey>,)+
+        }
+
+        impl $builder {
+            pub fn new(program_id: &Pubkey) -> Self {
+                Self {
+                    program_id: *program_id,
+                    $($field: None,)+
+                }
+            }
+
+            $(pub fn $field(mut self, key: Pubkey) -> Self {
+                self.$field = Some(key);
+                self
+            })+
+
+            /// Returns the accounts, fails on the first one that is neither set nor defaulted.
+            pub fn accounts(&self) -> Result<$accounts, BuildError> {
+                Ok($accounts {
+                    $($field: self
+                        .$field
+                        $(.or(Some($default)))?
+                        .ok_or(BuildError::MissingAccount(stringify!($field)))?,)+
+                })
+            }
+        }
+    };
+}
+
+amm_builder! {
+    /// Builds a 'withdraw' instruction from accounts set by name.
+    ///
+    /// ```ignore
+    /// let instruction = WithdrawBuilder::new(&program_id)
+    ///     .amm_id(amm_id)
+    ///     // ... the other accounts of WithdrawAccounts
+    ///     .user_owner(owner)
+    ///     .build(amount)?;
+    /// ```
+    WithdrawBuilder => WithdrawAccounts {
+        spl_token_program = spl_token::id(),
+        amm_id,
+        amm_authority,
+        amm_open_orders,
+        amm_target_orders,
+        lp_mint_address,
+        pool_coin_token_account,
+        pool_pc_token_account,
+        pool_withdraw_queue,
+        pool_temp_lp_token_account,
+        serum_program_id,
+        serum_market,
+        serum_coin_vault_account,
+        serum_pc_vault_account,
+        serum_vault_signer,
+        user_lp_token_account,
+        user_coin_token_account,
+        user_pc_token_account,
+        user_owner,
+    }
+}
+
+impl WithdrawBuilder {
+    /// Creates the 'withdraw' instruction of `amount` lp tokens.
+    pub fn build(&self, amount: u64) -> Result<Instruction, BuildError> {
+        let accounts = self.accounts()?.to_account_metas();
+        let data = AmmInstruction::Withdraw(WithdrawInstruction { amount }).pack()?;
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        })
+    }
+}
+
+amm_builder! {
+    /// Builds a 'swap' instruction from accounts set by name, see [WithdrawBuilder](struct.WithdrawBuilder.html).
+    SwapBuilder => SwapAccounts {
+        spl_token_program = spl_token::id(),
+        amm_id,
+        amm_authority,
+        amm_open_orders,
+        amm_target_orders,
+        pool_coin_token_account,
+        pool_pc_token_account,
+        serum_program_id,
+        serum_market,
+        serum_bids,
+        serum_asks,
+        serum_event_queue,
+        serum_coin_vault_account,
+        serum_pc_vault_account,
+        serum_vault_signer,
+        user_source_token_account,
+        user_destination_token_account,
+        user_source_owner,
+    }
+}
+
+impl SwapBuilder {
+    /// Creates the 'swap' instruction of `amount_in` for at least `minimum_amount_out`.
+    pub fn build(&self, amount_in: u64, minimum_amount_out: u64) -> Result<Instruction, BuildError> {
+        let accounts = self.accounts()?.to_account_metas();
+        let data = AmmInstruction::Swap(SwapInstruction {
+            amount_in,
+            minimum_amount_out,
+        })
+        .pack()?;
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        })
+    }
+}
+
+impl ProgramInstruction for AmmInstruction {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::Initialize(_) => 0,
+            Self::Initialize2 => 1,
+            Self::MonitorStep(_) => 2,
+            Self::Deposit(_) => 3,
+            Self::Withdraw(_) => 4,
+            Self::WithdrawTransfer(_) => 5,
+            Self::SetParams(_) => 6,
+            Self::WithdrawPnl => 7,
+            Self::WithdrawSrm(_) => 8,
+            Self::Swap(_) => 9,
+            Self::PreInitialize(_) => 10,
+        }
+    }
+
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Self::Initialize(_) => "Initialize",
+            Self::Initialize2 => "Initialize2",
+            Self::MonitorStep(_) => "MonitorStep",
+            Self::Deposit(_) => "Deposit",
+            Self::Withdraw(_) => "Withdraw",
+            Self::WithdrawTransfer(_) => "WithdrawTransfer",
+            Self::SetParams(_) => "SetParams",
+            Self::WithdrawPnl => "WithdrawPnl",
+            Self::WithdrawSrm(_) => "WithdrawSrm",
+            Self::Swap(_) => "Swap",
+            Self::PreInitialize(_) => "PreInitialize",
+        }
+    }
+
+    fn pack(&self) -> Result<Vec<u8>, ProgramError> {
+        AmmInstruction::pack(self)
+    }
+
+    fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        AmmInstruction::unpack(input)
+    }
+
+    fn expected_account_count(&self) -> AccountCount {
+        match self {
+            Self::Initialize(_) => AccountCount::exactly(InitializeAccounts::<Pubkey>::LEN),
+            Self::Initialize2 => AccountCount::between(Initialize2Accounts::<Pubkey>::LEN, Initialize2Accounts::<Pubkey>::LEN + 1),
+            Self::MonitorStep(_) => AccountCount::between(MonitorStepAccounts::<Pubkey>::LEN, MonitorStepAccounts::<Pubkey>::LEN + 1),
+            Self::Deposit(_) => AccountCount::exactly(DepositAccounts::<Pubkey>::LEN),
+            Self::Withdraw(_) => AccountCount::exactly(WithdrawAccounts::<Pubkey>::LEN),
+            Self::WithdrawTransfer(_) => AccountCount::at_least(WithdrawTransferAccounts::<Pubkey>::LEN),
+            Self::SetParams(instruction) => match SetParams::try_from(*instruction) {
+                Ok(params) => AccountCount::exactly(SetParamsAccounts::<Pubkey>::len_with(&params)),
+                Err(_) => AccountCount::between(SetParamsAccounts::<Pubkey>::LEN, SetParamsAccounts::<Pubkey>::LEN + 2),
+            },
+            Self::WithdrawPnl => AccountCount::exactly(WithdrawPnlAccounts::<Pubkey>::LEN),
+            Self::WithdrawSrm(_) => AccountCount::exactly(WithdrawSrmAccounts::<Pubkey>::LEN),
+            Self::Swap(_) => AccountCount::exactly(SwapAccounts::<Pubkey>::LEN),
+            Self::PreInitialize(_) => AccountCount::exactly(PreInitializeAccounts::<Pubkey>::LEN),
+        }
+    }
+
+    fn abi() -> ProgramAbi {
+        let u64_field = |name: &str| FieldAbi::new(name, "u64");
+        // The fixed fields come from the layouts, only the fields after the `SetParams` head are described here
+        let inputs = |tag: u8| InstructionLayout::find(Self::LAYOUTS, tag).abi_inputs();
+        let owner_params = vec![AmmParams::AmmOwner as u64, AmmParams::PnlOwner as u64];
+        let fees_params = vec![AmmParams::Fees as u64];
+        let non_value_params = [fees_params.clone(), owner_params.clone()].concat();
+        ProgramAbi {
+            instructions: vec![
+                InstructionAbi::new(0, "Initialize", inputs(0), InitializeAccounts::<Pubkey>::abi_accounts())
+                    .inputs_name("InitializeInstruction")
+                    .op_name("initialize"),
+                InstructionAbi::new(1, "Initialize2", inputs(1), Initialize2Accounts::<Pubkey>::abi_accounts())
+                    .op_name("initialize2"),
+                InstructionAbi::new(
+                    2,
+                    "MonitorStep",
+                    inputs(2),
+                    MonitorStepAccounts::<Pubkey>::abi_accounts(),
+                )
+                .inputs_name("MonitorStepInstruction")
+                .op_name("monitor_step"),
+                InstructionAbi::new(
+                    3,
+                    "Deposit",
+                    inputs(3),
+                    DepositAccounts::<Pubkey>::abi_accounts(),
+                )
+                .inputs_name("DepositInstruction")
+                .op_name("deposit"),
+                InstructionAbi::new(4, "Withdraw", inputs(4), WithdrawAccounts::<Pubkey>::abi_accounts())
+                    .inputs_name("WithdrawInstruction")
+                    .op_name("withdraw"),
+                InstructionAbi::new(
+                    5,
+                    "WithdrawTransfer",
+                    inputs(5),
+                    WithdrawTransferAccounts::<Pubkey>::abi_accounts(),
+                )
+                .inputs_name("WithdrawTransferInstruction")
+                .op_name("withdraw_transfer"),
+                InstructionAbi::new(
+                    6,
+                    "SetParams",
+                    [
+                        inputs(6),
+                        vec![
+                            u64_field("value").when(Condition::not_in("param", non_value_params)),
+                            FieldAbi::new("new_pubkey", "Pubkey").when(Condition::is_in("param", owner_params)),
+                            FieldAbi::new("fees", "Fees").when(Condition::is_in("param", fees_params)),
+                        ],
+                    ]
+                    .concat(),
+                    SetParamsAccounts::<Pubkey>::abi_accounts(),
+                )
+                .inputs_name("SetParamsInstruction")
+                .op_name("set_params"),
+                InstructionAbi::new(7, "WithdrawPnl", inputs(7), WithdrawPnlAccounts::<Pubkey>::abi_accounts())
+                    .op_name("withdrawpnl"),
+                InstructionAbi::new(8, "WithdrawSrm", inputs(8), WithdrawSrmAccounts::<Pubkey>::abi_accounts())
+                    .inputs_name("WithdrawSrmInstruction")
+                    .op_name("withdrawsrm"),
+                InstructionAbi::new(
+                    9,
+                    "Swap",
+                    inputs(9),
+                    SwapAccounts::<Pubkey>::abi_accounts(),
+                )
+                .inputs_name("SwapInstruction")
+                .op_name("swap"),
+                InstructionAbi::new(10, "PreInitialize", inputs(10), PreInitializeAccounts::<Pubkey>::abi_accounts())
+                    .inputs_name("InitializeInstruction")
+                    .op_name("pre_initialize"),
+            ],
+            types: vec![TypeAbi::new_struct(
+                "Fees",
+                vec![
+                    u64_field("min_separate_numerator"),
+                    u64_field("min_separate_denominator"),
+                    u64_field("trade_fee_numerator"),
+                    u64_field("trade_fee_denominator"),
+                    u64_field("pnl_numerator"),
+                    u64_field("pnl_denominator"),
+                    u64_field("swap_fee_numerator"),
+                    u64_field("swap_fee_denominator"),
+                ],
+            )],
+        }
+    }
+}
+
+/// Addresses derived by the program for a pool. `$authority` owns the pool accounts and
+/// is derived from `[amm Account, nonce]`, the accounts created by
+/// [AmmInstruction::PreInitialize](../enum.AmmInstruction.html#variant.PreInitialize)
+/// are associated to the serum market by `[program id, serum market, seed]`.
+pub mod pda {
+    use super::{initialize, pre_initialize};
+    use crate::error::AmmError;
+    use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+    pub const AMM_ASSOCIATED_SEED: &[u8] = b"amm_associated_seed";
+    pub const TARGET_ASSOCIATED_SEED: &[u8] = b"target_associated_seed";
+    pub const OPEN_ORDER_ASSOCIATED_SEED: &[u8] = b"open_order_associated_seed";
+    pub const COIN_VAULT_ASSOCIATED_SEED: &[u8] = b"coin_vault_associated_seed";
+    pub const PC_VAULT_ASSOCIATED_SEED: &[u8] = b"pc_vault_associated_seed";
+    pub const LP_MINT_ASSOCIATED_SEED: &[u8] = b"lp_mint_associated_seed";
+    pub const TEMP_LP_TOKEN_ASSOCIATED_SEED: &[u8] = b"temp_lp_token_associated_seed";
+    pub const WITHDRAW_ASSOCIATED_SEED: &[u8] = b"withdraw_associated_seed";
+
+    /// Returns the authority of an amm Account and the highest valid nonce
+    pub fn find_authority(program_id: &Pubkey, amm_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[amm_id.as_ref()], program_id)
+    }
+
+    /// Derives the authority for a nonce, fails when the nonce gives an address on the curve
+    pub fn authority(program_id: &Pubkey, amm_id: &Pubkey, nonce: u8) -> Result<Pubkey, AmmError> {
+        Pubkey::create_program_address(&[amm_id.as_ref(), &[nonce]], program_id)
+            .map_err(|_| AmmError::InvalidProgramAddress)
+    }
+
+    /// Checks a nonce and authority pair before sending it to the program
+    pub fn check_authority(
+        program_id: &Pubkey,
+        amm_id: &Pubkey,
+        nonce: u8,
+        amm_authority: &Pubkey,
+    ) -> Result<(), AmmError> {
+        if authority(program_id, amm_id, nonce)? != *amm_authority {
+            return Err(AmmError::InvalidProgramAddress);
+        }
+        Ok(())
+    }
+
+    /// Derives the vault signer of a serum market from its nonce
+    pub fn vault_signer(
+        serum_program_id: &Pubkey,
+        serum_market: &Pubkey,
+        vault_signer_nonce: u64,
+    ) -> Result<Pubkey, AmmError> {
+        Pubkey::create_program_address(&[serum_market.as_ref(), &vault_signer_nonce.to_le_bytes()], serum_program_id)
+            .map_err(|_| AmmError::InvalidProgramAddress)
+    }
+
+    /// Returns the address associated to a serum market for a seed
+    pub fn associated_address(program_id: &Pubkey, serum_market: &Pubkey, seed: &[u8]) -> Pubkey {
+        Pubkey::find_program_address(&[program_id.as_ref(), serum_market.as_ref(), seed], program_id).0
+    }
+
+    /// Pool-side accounts of the initialize flow
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct PoolAddresses {
+        pub amm_id: Pubkey,
+        pub amm_authority: Pubkey,
+        pub nonce: u8,
+        pub amm_open_orders: Pubkey,
+        pub amm_target_orders: Pubkey,
+        pub lp_mint_address: Pubkey,
+        pub pool_coin_token_account: Pubkey,
+        pub pool_pc_token_account: Pubkey,
+        pub pool_withdraw_queue: Pubkey,
+        pub pool_temp_lp_token_account: Pubkey,
+    }
+
+    impl PoolAddresses {
+        pub fn new(program_id: &Pubkey, serum_market: &Pubkey) -> Self {
+            let associated = |seed| associated_address(program_id, serum_market, seed);
+            let amm_id = associated(AMM_ASSOCIATED_SEED);
+            let (amm_authority, nonce) = find_authority(program_id, &amm_id);
+            Self {
+                amm_id,
+                amm_authority,
+                nonce,
+                amm_open_orders: associated(OPEN_ORDER_ASSOCIATED_SEED),
+                amm_target_orders: associated(TARGET_ASSOCIATED_SEED),
+                lp_mint_address: associated(LP_MINT_ASSOCIATED_SEED),
+                pool_coin_token_account: associated(COIN_VAULT_ASSOCIATED_SEED),
+                pool_pc_token_account: associated(PC_VAULT_ASSOCIATED_SEED),
+                pool_withdraw_queue: associated(WITHDRAW_ASSOCIATED_SEED),
+                pool_temp_lp_token_account: associated(TEMP_LP_TOKEN_ASSOCIATED_SEED),
+            }
+        }
+
+        /// Creates the `PreInitialize` instruction of the pool
+        pub fn pre_initialize(
+            &self,
+            program_id: &Pubkey,
+            coin_mint_address: &Pubkey,
+            pc_mint_address: &Pubkey,
+            serum_market: &Pubkey,
+            user_wallet: &Pubkey,
+        ) -> Result<Instruction, ProgramError> {
+            pre_initialize(
+                program_id,
+                &self.amm_target_orders,
+                &self.pool_withdraw_queue,
+                &self.amm_authority,
+                &self.lp_mint_address,
+                coin_mint_address,
+                pc_mint_address,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.pool_temp_lp_token_account,
+                serum_market,
+                user_wallet,
+                self.nonce,
+            )
+        }
+
+        /// Creates the `Initialize` instruction of the pool, the initial lp tokens go to `pool_lp_token_account`
+        #[allow(clippy::too_many_arguments)]
+        pub fn initialize(
+            &self,
+            program_id: &Pubkey,
+            coin_mint_address: &Pubkey,
+            pc_mint_address: &Pubkey,
+            pool_lp_token_account: &Pubkey,
+            serum_program_id: &Pubkey,
+            serum_market: &Pubkey,
+            user_wallet: &Pubkey,
+        ) -> Result<Instruction, ProgramError> {
+            initialize(
+                program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.lp_mint_address,
+                coin_mint_address,
+                pc_mint_address,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.pool_withdraw_queue,
+                pool_lp_token_account,
+                &self.pool_temp_lp_token_account,
+                serum_program_id,
+                serum_market,
+                user_wallet,
+                self.nonce,
+            )
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use solana_program::pubkey::Pubkey;
+
+        use super::{authority, check_authority, find_authority, PoolAddresses};
+        use crate::error::AmmError;
+
+        #[test]
+        fn test_authority() {
+            let program_id = Pubkey::new_unique();
+            let amm_id = Pubkey::new_unique();
+            let (amm_authority, nonce) = find_authority(&program_id, &amm_id);
+            assert_eq!(authority(&program_id, &amm_id, nonce), Ok(amm_authority));
+            assert_eq!(check_authority(&program_id, &amm_id, nonce, &amm_authority), Ok(()));
+            assert_eq!(
+                check_authority(&program_id, &amm_id, nonce, &Pubkey::new_unique()),
+                Err(AmmError::InvalidProgramAddress)
+            );
+            // Any other nonce gives another address or none
+            for other in (0..=255u8).filter(|other| *other != nonce) {
+                assert!(check_authority(&program_id, &amm_id, other, &amm_authority).is_err());
+            }
+        }
+
+        #[test]
+        fn test_pool_addresses() {
+            let program_id = Pubkey::new_unique();
+            let serum_market = Pubkey::new_unique();
+            let addresses = PoolAddresses::new(&program_id, &serum_market);
+            assert_eq!(addresses, PoolAddresses::new(&program_id, &serum_market));
+            assert_eq!(
+                check_authority(&program_id, &addresses.amm_id, addresses.nonce, &addresses.amm_authority),
+                Ok(())
+            );
+            let user_wallet = Pubkey::new_unique();
+            let (coin_mint, pc_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+            let pre_initialize = addresses
+                .pre_initialize(&program_id, &coin_mint, &pc_mint, &serum_market, &user_wallet)
+                .unwrap();
+            assert_eq!(pre_initialize.accounts[5].pubkey, addresses.amm_authority);
+            assert_eq!(pre_initialize.data, vec![10, addresses.nonce]);
+            let initialize = addresses
+                .initialize(&program_id, &coin_mint, &pc_mint, &Pubkey::new_unique(), &Pubkey::new_unique(), &serum_market, &user_wallet)
+                .unwrap();
+            assert_eq!(initialize.accounts[3].pubkey, addresses.amm_id);
+            assert_eq!(initialize.accounts[9].pubkey, pre_initialize.accounts[9].pubkey);
+        }
+    }
+}
+
+/// The pool and serum market accounts of a pool, so the instructions only take
+/// the user accounts and amounts.
+///
+/// With the `serde` feature a [PoolRegistry](struct.PoolRegistry.html) is read from a JSON or TOML
+/// list of pools with `PoolRegistry::load`, the keys as base58 strings:
+///
+/// ```toml
+/// [[pools]]
+/// program_id = "..."
+/// amm_id = "..."
+/// nonce = 255                     # or amm_authority, derived from amm_id otherwise
+/// serum_vault_signer_nonce = 0    # or serum_vault_signer, checked against each other when both are set
+/// # ... the other fields of PoolKeys
+/// ```
+pub mod pool_keys {
+    use super::{
+        deposit, monitor_step, swap, withdraw, withdraw_transfer, withdraw_transfer_batches, BuildError,
+        WithdrawTransferAccounts,
+    };
+    use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct PoolKeys {
+        pub program_id: Pubkey,
+        pub amm_id: Pubkey,
+        pub amm_authority: Pubkey,
+        pub amm_open_orders: Pubkey,
+        pub amm_target_orders: Pubkey,
+        pub lp_mint_address: Pubkey,
+        pub pool_coin_token_account: Pubkey,
+        pub pool_pc_token_account: Pubkey,
+        pub pool_withdraw_queue: Pubkey,
+        pub pool_temp_lp_token_account: Pubkey,
+        pub serum_program_id: Pubkey,
+        pub serum_market: Pubkey,
+        pub serum_bids: Pubkey,
+        pub serum_asks: Pubkey,
+        pub serum_event_queue: Pubkey,
+        /// only needed by `monitor_step`
+        pub serum_req_q: Option<Pubkey>,
+        pub serum_coin_vault_account: Pubkey,
+        pub serum_pc_vault_account: Pubkey,
+        pub serum_vault_signer: Pubkey,
+    }
+
+    impl PoolKeys {
+        /// Creates the `Deposit` instruction of the pool
+        pub fn deposit(
+            &self,
+            user_coin_token_account: &Pubkey,
+            user_pc_token_account: &Pubkey,
+            user_lp_token_account: &Pubkey,
+            user_owner: &Pubkey,
+            max_coin_amount: u64,
+            max_pc_amount: u64,
+            base_side: u64,
+        ) -> Result<Instruction, ProgramError> {
+            deposit(
+                &self.program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.amm_target_orders,
+                &self.lp_mint_address,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.serum_market,
+                user_coin_token_account,
+                user_pc_token_account,
+                user_lp_token_account,
+                user_owner,
+                max_coin_amount,
+                max_pc_amount,
+                base_side,
+            )
+        }
+
+        /// Creates the `Withdraw` instruction of `amount` lp tokens
+        pub fn withdraw(
+            &self,
+            user_lp_token_account: &Pubkey,
+            user_coin_token_account: &Pubkey,
+            user_pc_token_account: &Pubkey,
+            user_owner: &Pubkey,
+            amount: u64,
+        ) -> Result<Instruction, ProgramError> {
+            withdraw(
+                &self.program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.amm_target_orders,
+                &self.lp_mint_address,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.pool_withdraw_queue,
+                &self.pool_temp_lp_token_account,
+                &self.serum_program_id,
+                &self.serum_market,
+                &self.serum_coin_vault_account,
+                &self.serum_pc_vault_account,
+                &self.serum_vault_signer,
+                user_lp_token_account,
+                user_coin_token_account,
+                user_pc_token_account,
+                user_owner,
+                amount,
+            )
+        }
+
+        /// Creates the `Swap` instruction of `amount_in` for at least `minimum_amount_out`
+        pub fn swap(
+            &self,
+            user_source_token_account: &Pubkey,
+            user_destination_token_account: &Pubkey,
+            user_source_owner: &Pubkey,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> Result<Instruction, ProgramError> {
+            swap(
+                &self.program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.amm_target_orders,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.serum_program_id,
+                &self.serum_market,
+                &self.serum_bids,
+                &self.serum_asks,
+                &self.serum_event_queue,
+                &self.serum_coin_vault_account,
+                &self.serum_pc_vault_account,
+                &self.serum_vault_signer,
+                user_source_token_account,
+                user_destination_token_account,
+                user_source_owner,
+                amount_in,
+                minimum_amount_out,
+            )
+        }
+
+        /// Creates the `WithdrawTransfer` instruction paying out the withdraw queue to `withdraw_dest_accounts`
+        pub fn withdraw_transfer(&self, withdraw_dest_accounts: &[Pubkey], limit: u16) -> Result<Instruction, BuildError> {
+            withdraw_transfer(
+                &self.program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.lp_mint_address,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.pool_withdraw_queue,
+                &self.pool_temp_lp_token_account,
+                &self.serum_program_id,
+                &self.serum_market,
+                &self.serum_coin_vault_account,
+                &self.serum_pc_vault_account,
+                &self.serum_vault_signer,
+                withdraw_dest_accounts,
+                limit,
+            )
+        }
+
+        /// Creates the `WithdrawTransfer` instructions paying out the withdraw queue, see
+        /// [withdraw_transfer_batches](../fn.withdraw_transfer_batches.html)
+        pub fn withdraw_transfer_batches(&self, withdraw_dest_accounts: &[Pubkey]) -> Result<Vec<Instruction>, BuildError> {
+            let accounts = WithdrawTransferAccounts {
+                spl_token_program: spl_token::id(),
+                amm_id: self.amm_id,
+                amm_authority: self.amm_authority,
+                amm_open_orders: self.amm_open_orders,
+                lp_mint_address: self.lp_mint_address,
+                pool_coin_token_account: self.pool_coin_token_account,
+                pool_pc_token_account: self.pool_pc_token_account,
+                pool_withdraw_queue: self.pool_withdraw_queue,
+                pool_temp_lp_token_account: self.pool_temp_lp_token_account,
+                serum_program_id: self.serum_program_id,
+                serum_market: self.serum_market,
+                serum_coin_vault_account: self.serum_coin_vault_account,
+                serum_pc_vault_account: self.serum_pc_vault_account,
+                serum_vault_signer: self.serum_vault_signer,
+                withdraw_dest_accounts: withdraw_dest_accounts.to_vec(),
+            };
+            withdraw_transfer_batches(&self.program_id, &accounts)
+        }
+
+        /// Creates the `MonitorStep` instruction, fails with `NotEnoughAccountKeys` without `serum_req_q`
+        pub fn monitor_step(
+            &self,
+            srm_token_account: Option<Pubkey>,
+            plan_order_limit: u16,
+            place_order_limit: u16,
+            cancel_order_limit: u16,
+        ) -> Result<Instruction, ProgramError> {
+            let serum_req_q = self.serum_req_q.ok_or(BuildError::MissingAccount("serum_req_q"))?;
+            monitor_step(
+                &self.program_id,
+                &self.amm_id,
+                &self.amm_authority,
+                &self.amm_open_orders,
+                &self.amm_target_orders,
+                &self.pool_coin_token_account,
+                &self.pool_pc_token_account,
+                &self.pool_withdraw_queue,
+                srm_token_account,
+                &self.serum_program_id,
+                &self.serum_market,
+                &self.serum_coin_vault_account,
+                &self.serum_pc_vault_account,
+                &self.serum_vault_signer,
+                &serum_req_q,
+                &self.serum_event_queue,
+                &self.serum_bids,
+                &self.serum_asks,
+                plan_order_limit,
+                place_order_limit,
+                cancel_order_limit,
+            )
+        }
+    }
+
+    /// The pools of a registry file
+    #[derive(Clone, Debug, Default, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+    pub struct PoolRegistry {
+        pub pools: Vec<PoolKeys>,
+    }
+
+    impl PoolRegistry {
+        pub fn get(&self, amm_id: &Pubkey) -> Option<&PoolKeys> {
+            self.pools.iter().find(|pool| pool.amm_id == *amm_id)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl PoolRegistry {
+        pub fn from_json_str(json: &str) -> Result<Self, RegistryError> {
+            Ok(serde_json::from_str(json)?)
+        }
+
+        pub fn from_toml_str(toml: &str) -> Result<Self, RegistryError> {
+            Ok(toml::from_str(toml)?)
+        }
+
+        /// Reads a registry file, TOML when its extension is `.toml` and JSON otherwise
+        pub fn load<P: AsRef<std::path::Path>>(path: P) -> Result<Self, RegistryError> {
+            let path = path.as_ref();
+            let content = std::fs::read_to_string(path)?;
+            match path.extension() {
+                Some(extension) if extension == "toml" => Self::from_toml_str(&content),
+                _ => Self::from_json_str(&content),
+            }
+        }
+    }
+
+    /// Reason why a [PoolRegistry](struct.PoolRegistry.html) could not be read
+    #[cfg(feature = "serde")]
+    #[derive(Debug)]
+    pub enum RegistryError {
+        Io(std::io::Error),
+        Json(serde_json::Error),
+        Toml(toml::de::Error),
+    }
+
+    #[cfg(feature = "serde")]
+    impl std::fmt::Display for RegistryError {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match self {
+                Self::Io(err) => write!(f, "failed to read the pool registry: {}", err),
+                Self::Json(err) => write!(f, "invalid JSON pool registry: {}", err),
+                Self::Toml(err) => write!(f, "invalid TOML pool registry: {}", err),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl std::error::Error for RegistryError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Self::Io(err) => Some(err),
+                Self::Json(err) => Some(err),
+                Self::Toml(err) => Some(err),
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl From<std::io::Error> for RegistryError {
+        fn from(err: std::io::Error) -> Self {
+            Self::Io(err)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl From<serde_json::Error> for RegistryError {
+        fn from(err: serde_json::Error) -> Self {
+            Self::Json(err)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl From<toml::de::Error> for RegistryError {
+        fn from(err: toml::de::Error) -> Self {
+            Self::Toml(err)
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    mod serde_keys {
+        use super::PoolKeys;
+        use crate::instruction::pda;
+        use serde::{de::Error, Deserialize, Deserializer};
+        use solana_program::pubkey::Pubkey;
+        use std::str::FromStr;
+
+        /// A pubkey as a base58 string
+        struct Key(Pubkey);
+
+        impl<'de> Deserialize<'de> for Key {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let key = String::deserialize(deserializer)?;
+                Pubkey::from_str(&key)
+                    .map(Key)
+                    .map_err(|_| D::Error::custom(format!("invalid pubkey {:?}", key)))
+            }
+        }
+
+        /// A registry entry, the authority and vault signer can be given by their nonce
+        #[derive(Deserialize)]
+        struct PoolEntry {
+            program_id: Key,
+            amm_id: Key,
+            amm_authority: Option<Key>,
+            nonce: Option<u8>,
+            amm_open_orders: Key,
+            amm_target_orders: Key,
+            lp_mint_address: Key,
+            pool_coin_token_account: Key,
+            pool_pc_token_account: Key,
+            pool_withdraw_queue: Key,
+            pool_temp_lp_token_account: Key,
+            serum_program_id: Key,
+            serum_market: Key,
+            serum_bids: Key,
+            serum_asks: Key,
+            serum_event_queue: Key,
+            serum_req_q: Option<Key>,
+            serum_coin_vault_account: Key,
+            serum_pc_vault_account: Key,
+            serum_vault_signer: Option<Key>,
+            serum_vault_signer_nonce: Option<u64>,
+        }
+
+        impl<'de> Deserialize<'de> for PoolKeys {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let entry = PoolEntry::deserialize(deserializer)?;
+                let (program_id, amm_id) = (entry.program_id.0, entry.amm_id.0);
+                let amm_authority = match (entry.amm_authority, entry.nonce) {
+                    (Some(Key(amm_authority)), Some(nonce)) => {
+                        pda::check_authority(&program_id, &amm_id, nonce, &amm_authority)
+                            .map_err(|_| D::Error::custom("amm_authority doesn't match the nonce"))?;
+                        amm_authority
+                    }
+                    (Some(Key(amm_authority)), None) => amm_authority,
+                    (None, Some(nonce)) => pda::authority(&program_id, &amm_id, nonce)
+                        .map_err(|_| D::Error::custom("invalid nonce"))?,
+                    (None, None) => pda::find_authority(&program_id, &amm_id).0,
+                };
+                let (serum_program_id, serum_market) = (entry.serum_program_id.0, entry.serum_market.0);
+                let serum_vault_signer = match (entry.serum_vault_signer, entry.serum_vault_signer_nonce) {
+                    (Some(Key(serum_vault_signer)), Some(nonce)) => {
+                        match pda::vault_signer(&serum_program_id, &serum_market, nonce) {
+                            Ok(derived) if derived == serum_vault_signer => serum_vault_signer,
+                            _ => {
+                                return Err(D::Error::custom(
+                                    "serum_vault_signer doesn't match the serum_vault_signer_nonce",
+                                ))
+                            }
+                        }
+                    }
+                    (Some(Key(serum_vault_signer)), None) => serum_vault_signer,
+                    (None, Some(nonce)) => pda::vault_signer(&serum_program_id, &serum_market, nonce)
+                        .map_err(|_| D::Error::custom("invalid serum_vault_signer_nonce"))?,
+                    (None, None) => {
+                        return Err(D::Error::custom(
+                            "missing serum_vault_signer or serum_vault_signer_nonce",
+                        ))
+                    }
+                };
+                Ok(PoolKeys {
+                    program_id,
+                    amm_id,
+                    amm_authority,
+                    amm_open_orders: entry.amm_open_orders.0,
+                    amm_target_orders: entry.amm_target_orders.0,
+                    lp_mint_address: entry.lp_mint_address.0,
+                    pool_coin_token_account: entry.pool_coin_token_account.0,
+                    pool_pc_token_account: entry.pool_pc_token_account.0,
+                    pool_withdraw_queue: entry.pool_withdraw_queue.0,
+                    pool_temp_lp_token_account: entry.pool_temp_lp_token_account.0,
+                    serum_program_id,
+                    serum_market,
+                    serum_bids: entry.serum_bids.0,
+                    serum_asks: entry.serum_asks.0,
+                    serum_event_queue: entry.serum_event_queue.0,
+                    serum_req_q: entry.serum_req_q.map(|key| key.0),
+                    serum_coin_vault_account: entry.serum_coin_vault_account.0,
+                    serum_pc_vault_account: entry.serum_pc_vault_account.0,
+                    serum_vault_signer,
+                })
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+        #[cfg(feature = "serde")]
+        use super::RegistryError;
+        use super::{PoolKeys, PoolRegistry};
+        use crate::instruction::{pda, AmmInstruction, BuildError, SwapAccounts};
+
+        fn pool_keys() -> PoolKeys {
+            let program_id = Pubkey::new_unique();
+            let amm_id = Pubkey::new_unique();
+            PoolKeys {
+                program_id,
+                amm_id,
+                amm_authority: pda::find_authority(&program_id, &amm_id).0,
+                amm_open_orders: Pubkey::new_unique(),
+                amm_target_orders: Pubkey::new_unique(),
+                lp_mint_address: Pubkey::new_unique(),
+                pool_coin_token_account: Pubkey::new_unique(),
+                pool_pc_token_account: Pubkey::new_unique(),
+                pool_withdraw_queue: Pubkey::new_unique(),
+                pool_temp_lp_token_account: Pubkey::new_unique(),
+                serum_program_id: Pubkey::new_unique(),
+                serum_market: Pubkey::new_unique(),
+                serum_bids: Pubkey::new_unique(),
+                serum_asks: Pubkey::new_unique(),
+                serum_event_queue: Pubkey::new_unique(),
+                serum_req_q: None,
+                serum_coin_vault_account: Pubkey::new_unique(),
+                serum_pc_vault_account: Pubkey::new_unique(),
+                serum_vault_signer: Pubkey::new_unique(),
+            }
+        }
+
+        #[test]
+        fn test_pool_keys() {
+            let pool = pool_keys();
+            let (source, destination, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+            let instruction = pool.swap(&source, &destination, &owner, 100, 90).unwrap();
+            assert_eq!(instruction.program_id, pool.program_id);
+            assert_eq!(
+                instruction.accounts,
+                SwapAccounts {
+                    spl_token_program: spl_token::id(),
+                    amm_id: pool.amm_id,
+                    amm_authority: pool.amm_authority,
+                    amm_open_orders: pool.amm_open_orders,
+                    amm_target_orders: pool.amm_target_orders,
+                    pool_coin_token_account: pool.pool_coin_token_account,
+                    pool_pc_token_account: pool.pool_pc_token_account,
+                    serum_program_id: pool.serum_program_id,
+                    serum_market: pool.serum_market,
+                    serum_bids: pool.serum_bids,
+                    serum_asks: pool.serum_asks,
+                    serum_event_queue: pool.serum_event_queue,
+                    serum_coin_vault_account: pool.serum_coin_vault_account,
+                    serum_pc_vault_account: pool.serum_pc_vault_account,
+                    serum_vault_signer: pool.serum_vault_signer,
+                    user_source_token_account: source,
+                    user_destination_token_account: destination,
+                    user_source_owner: owner,
+                }
+                .to_account_metas()
+            );
+
+            let deposit = pool.deposit(&source, &destination, &Pubkey::new_unique(), &owner, 1, 2, 0).unwrap();
+            assert_eq!(deposit.accounts[1].pubkey, pool.amm_id);
+            assert_eq!(deposit.accounts[12].pubkey, owner);
+            let withdraw = pool.withdraw(&Pubkey::new_unique(), &source, &destination, &owner, 5).unwrap();
+            assert_eq!(AmmInstruction::unpack(&withdraw.data), AmmInstruction::unpack(&[4, 5, 0, 0, 0, 0, 0, 0, 0]));
+            assert_eq!(withdraw.accounts[17].pubkey, destination);
+
+            let dest = [Pubkey::new_unique(), Pubkey::new_unique()];
+            let transfer = pool.withdraw_transfer(&dest, 1).unwrap();
+            assert_eq!(
+                pool.withdraw_transfer(&dest, 2),
+                Err(BuildError::WithdrawLimitTooHigh { limit: 2, entries: 1 })
+            );
+            assert_eq!(pool.withdraw_transfer(&dest[..1], 0), Err(BuildError::UnpairedWithdrawDestinations(1)));
+            assert_eq!(transfer.accounts.len(), 16);
+            assert_eq!(transfer.accounts[15].pubkey, dest[1]);
+            assert_eq!(pool.withdraw_transfer_batches(&dest), Ok(vec![transfer]));
+
+            assert_eq!(pool.monitor_step(None, 1, 1, 1), Err(ProgramError::NotEnoughAccountKeys));
+            let serum_req_q = Pubkey::new_unique();
+            let pool = PoolKeys {
+                serum_req_q: Some(serum_req_q),
+                ..pool
+            };
+            let monitor_step = pool.monitor_step(None, 1, 1, 1).unwrap();
+            assert_eq!(monitor_step.accounts[15].pubkey, serum_req_q);
+
+            let registry = PoolRegistry { pools: vec![pool_keys(), pool] };
+            assert_eq!(registry.get(&pool.amm_id), Some(&pool));
+            assert_eq!(registry.get(&Pubkey::new_unique()), None);
+        }
+
+        #[cfg(feature = "serde")]
+        fn registry_json(pool: &PoolKeys) -> serde_json::Value {
+            serde_json::json!({
+                "pools": [{
+                    "program_id": pool.program_id.to_string(),
+                    "amm_id": pool.amm_id.to_string(),
+                    "amm_open_orders": pool.amm_open_orders.to_string(),
+                    "amm_target_orders": pool.amm_target_orders.to_string(),
+                    "lp_mint_address": pool.lp_mint_address.to_string(),
+                    "pool_coin_token_account": pool.pool_coin_token_account.to_string(),
+                    "pool_pc_token_account": pool.pool_pc_token_account.to_string(),
+                    "pool_withdraw_queue": pool.pool_withdraw_queue.to_string(),
+                    "pool_temp_lp_token_account": pool.pool_temp_lp_token_account.to_string(),
+                    "serum_program_id": pool.serum_program_id.to_string(),
+                    "serum_market": pool.serum_market.to_string(),
+                    "serum_bids": pool.serum_bids.to_string(),
+                    "serum_asks": pool.serum_asks.to_string(),
+                    "serum_event_queue": pool.serum_event_queue.to_string(),
+                    "serum_coin_vault_account": pool.serum_coin_vault_account.to_string(),
+                    "serum_pc_vault_account": pool.serum_pc_vault_account.to_string(),
+                    "serum_vault_signer": pool.serum_vault_signer.to_string(),
+                    // registries list more than the accounts
+                    "coin_decimals": 6,
+                }]
+            })
+        }
+
+        #[test]
+        #[cfg(feature = "serde")]
+        fn test_registry() {
+            let pool = pool_keys();
+            let json = registry_json(&pool);
+            // The authority is found from amm_id
+            let registry = PoolRegistry::from_json_str(&json.to_string()).unwrap();
+            assert_eq!(registry.pools, vec![pool]);
+            assert!(matches!(PoolRegistry::from_json_str("{}"), Err(RegistryError::Json(_))));
+
+            let (_, nonce) = pda::find_authority(&pool.program_id, &pool.amm_id);
+            let mut entry = json["pools"][0].clone();
+            entry["nonce"] = nonce.into();
+            entry["amm_authority"] = pool.amm_authority.to_string().into();
+            let keys: PoolKeys = serde_json::from_value(entry.clone()).unwrap();
+            assert_eq!(keys, pool);
+            entry["amm_authority"] = Pubkey::new_unique().to_string().into();
+            assert!(serde_json::from_value::<PoolKeys>(entry.clone()).is_err());
+            entry.as_object_mut().unwrap().remove("amm_authority");
+            assert_eq!(serde_json::from_value::<PoolKeys>(entry.clone()).unwrap(), pool);
+
+            entry.as_object_mut().unwrap().remove("serum_vault_signer");
+            assert!(serde_json::from_value::<PoolKeys>(entry.clone()).is_err());
+            let vault_signer_nonce = (0..)
+                .find(|nonce| pda::vault_signer(&pool.serum_program_id, &pool.serum_market, *nonce).is_ok())
+                .unwrap();
+            entry["serum_vault_signer_nonce"] = vault_signer_nonce.into();
+            let keys: PoolKeys = serde_json::from_value(entry.clone()).unwrap();
+            assert_eq!(
+                Ok(keys.serum_vault_signer),
+                pda::vault_signer(&pool.serum_program_id, &pool.serum_market, vault_signer_nonce)
+            );
+            // Both given: they have to agree
+            entry["serum_vault_signer"] = keys.serum_vault_signer.to_string().into();
+            assert_eq!(serde_json::from_value::<PoolKeys>(entry.clone()).unwrap(), keys);
+            entry["serum_vault_signer"] = pool.serum_vault_signer.to_string().into();
+            let err = serde_json::from_value::<PoolKeys>(entry.clone()).unwrap_err();
+            assert!(err.to_string().contains("doesn't match the serum_vault_signer_nonce"));
+            entry.as_object_mut().unwrap().remove("serum_vault_signer");
+
+            entry["amm_id"] = "not a pubkey".into();
+            let err = serde_json::from_value::<PoolKeys>(entry).unwrap_err();
+            assert!(err.to_string().contains("invalid pubkey"));
+
+            let mut toml = String::from("[[pools]]\n");
+            for (name, value) in json["pools"][0].as_object().unwrap() {
+                toml.push_str(&format!("{} = {}\n", name, value));
+            }
+            let registry = PoolRegistry::from_toml_str(&toml).unwrap();
+            assert_eq!(registry.get(&pool.amm_id), Some(&pool));
+            assert!(matches!(PoolRegistry::from_toml_str("pools = 1"), Err(RegistryError::Toml(_))));
+
+            let dir = std::env::temp_dir().join(format!("raydium-registry-{}", pool.amm_id));
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("pools.toml"), &toml).unwrap();
+            std::fs::write(dir.join("pools.json"), json.to_string()).unwrap();
+            assert_eq!(PoolRegistry::load(dir.join("pools.toml")).unwrap(), registry);
+            assert_eq!(PoolRegistry::load(dir.join("pools.json")).unwrap(), registry);
+            assert!(matches!(PoolRegistry::load(dir.join("missing.json")), Err(RegistryError::Io(_))));
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
+
+/// Constant-product quotes of [AmmInstruction::Swap](enum.AmmInstruction.html#variant.Swap),
+/// rounded as the program does so the quoted output is never more than the swap gives.
+pub mod quote {
+    use super::SwapInstruction;
+    use crate::state::Fees;
+
+    const BPS: u128 = 10_000;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum SwapDirection {
+        CoinToPc,
+        PcToCoin,
+    }
+
+    /// Coin and pc amounts of the pool, vaults plus open orders without the pnl to take
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct PoolReserves {
+        pub coin: u64,
+        pub pc: u64,
+    }
+
+    impl PoolReserves {
+        /// (input, output) reserves for a direction
+        fn for_direction(&self, direction: SwapDirection) -> (u128, u128) {
+            match direction {
+                SwapDirection::CoinToPc => (self.coin as u128, self.pc as u128),
+                SwapDirection::PcToCoin => (self.pc as u128, self.coin as u128),
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum QuoteError {
+        /// One of the reserves is empty
+        EmptyPool,
+        /// The requested output is not below the output reserve
+        InsufficientLiquidity,
+        /// The swap fee denominator is zero or below its numerator
+        InvalidFees,
+        /// Slippage above 10000 basis points
+        InvalidSlippage,
+        Overflow,
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct Quote {
+        pub amount_in: u64,
+        /// Part of `amount_in` kept as swap fee
+        pub swap_fee: u64,
+        pub amount_out: u64,
+        /// `amount_out` less the slippage tolerance
+        pub minimum_amount_out: u64,
+        /// Price move caused by the swap, fee excluded, rounded up
+        pub price_impact_bps: u64,
+    }
+
+    impl Quote {
+        pub fn swap_instruction(&self) -> SwapInstruction {
+            SwapInstruction {
+                amount_in: self.amount_in,
+                minimum_amount_out: self.minimum_amount_out,
+            }
+        }
+    }
+
+    fn ceil_div(numerator: u128, denominator: u128) -> u128 {
+        let quotient = numerator / denominator;
+        if quotient * denominator < numerator {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+
+    fn to_u64(value: u128) -> Result<u64, QuoteError> {
+        if value > u64::MAX as u128 {
+            return Err(QuoteError::Overflow);
+        }
+        Ok(value as u64)
+    }
+
+    fn check(reserves: &PoolReserves, fees: &Fees, slippage_bps: u16) -> Result<(), QuoteError> {
+        if reserves.coin == 0 || reserves.pc == 0 {
+            return Err(QuoteError::EmptyPool);
+        }
+        if fees.swap_fee_denominator == 0 || fees.swap_fee_numerator > fees.swap_fee_denominator {
+            return Err(QuoteError::InvalidFees);
+        }
+        if slippage_bps as u128 > BPS {
+            return Err(QuoteError::InvalidSlippage);
+        }
+        Ok(())
+    }
+
+    /// Fee rounded up as charged by the program
+    fn swap_fee(amount_in: u64, fees: &Fees) -> u64 {
+        ceil_div(
+            amount_in as u128 * fees.swap_fee_numerator as u128,
+            fees.swap_fee_denominator as u128,
+        ) as u64
+    }
+
+    fn quote(
+        reserves: &PoolReserves,
+        direction: SwapDirection,
+        amount_in: u64,
+        fees: &Fees,
+        slippage_bps: u16,
+    ) -> Quote {
+        let (reserve_in, reserve_out) = reserves.for_direction(direction);
+        let swap_fee = swap_fee(amount_in, fees);
+        let amount_in_after_fee = (amount_in - swap_fee) as u128;
+        let amount_out = reserve_out * amount_in_after_fee / (reserve_in + amount_in_after_fee);
+        let minimum_amount_out = amount_out * (BPS - slippage_bps as u128) / BPS;
+        let price_impact_bps = ceil_div(amount_in_after_fee * BPS, reserve_in + amount_in_after_fee);
+        Quote {
+            amount_in,
+            swap_fee,
+            amount_out: amount_out as u64,
+            minimum_amount_out: minimum_amount_out as u64,
+            price_impact_bps: price_impact_bps as u64,
+        }
+    }
+
+    /// Quotes swapping `amount_in`, `slippage_bps` sets `minimum_amount_out`
+    pub fn quote_exact_in(
+        reserves: &PoolReserves,
+        direction: SwapDirection,
+        amount_in: u64,
+        fees: &Fees,
+        slippage_bps: u16,
+    ) -> Result<Quote, QuoteError> {
+        check(reserves, fees, slippage_bps)?;
+        Ok(quote(reserves, direction, amount_in, fees, slippage_bps))
+    }
+
+    /// Quotes the smallest `amount_in` giving at least `amount_out`, which
+    /// becomes `minimum_amount_out`
+    pub fn quote_exact_out(
+        reserves: &PoolReserves,
+        direction: SwapDirection,
+        amount_out: u64,
+        fees: &Fees,
+    ) -> Result<Quote, QuoteError> {
+        check(reserves, fees, 0)?;
+        let (reserve_in, reserve_out) = reserves.for_direction(direction);
+        if amount_out as u128 >= reserve_out {
+            return Err(QuoteError::InsufficientLiquidity);
+        }
+        let amount_in_after_fee = ceil_div(reserve_in * amount_out as u128, reserve_out - amount_out as u128);
+        let fee_denominator = fees.swap_fee_denominator as u128;
+        let fee_rate = fee_denominator - fees.swap_fee_numerator as u128;
+        if fee_rate == 0 {
+            return Err(QuoteError::InvalidFees);
+        }
+        let mut amount_in = to_u64(ceil_div(amount_in_after_fee * fee_denominator, fee_rate))?;
+        // The rounded up fee can take one more unit than the estimate
+        let mut quote = quote(reserves, direction, amount_in, fees, 0);
+        while quote.amount_out < amount_out {
+            amount_in = amount_in.checked_add(1).ok_or(QuoteError::Overflow)?;
+            quote = self::quote(reserves, direction, amount_in, fees, 0);
+        }
+        // and the estimate can be above the smallest amount
+        while amount_in > 0 {
+            let lower = self::quote(reserves, direction, amount_in - 1, fees, 0);
+            if lower.amount_out < amount_out {
+                break;
+            }
+            amount_in -= 1;
+            quote = lower;
+        }
+        quote.minimum_amount_out = amount_out;
+        Ok(quote)
+    }
+
+    #[cfg(test)]
+    mod test {
+        use proptest::prelude::*;
+
+        use super::{quote_exact_in, quote_exact_out, PoolReserves, QuoteError, SwapDirection};
+        use crate::state::Fees;
+
+        fn fees(swap_fee_numerator: u64, swap_fee_denominator: u64) -> Fees {
+            Fees {
+                min_separate_numerator: 5,
+                min_separate_denominator: 10000,
+                trade_fee_numerator: 25,
+                trade_fee_denominator: 10000,
+                pnl_numerator: 12,
+                pnl_denominator: 100,
+                swap_fee_numerator,
+                swap_fee_denominator,
+            }
+        }
+
+        #[test]
+        fn test_quote() {
+            let reserves = PoolReserves { coin: 1_000_000, pc: 2_000_000 };
+            let quote = quote_exact_in(&reserves, SwapDirection::CoinToPc, 10_000, &fees(25, 10000), 100).unwrap();
+            assert_eq!(quote.swap_fee, 25);
+            // 2_000_000 * 9_975 / 1_009_975
+            assert_eq!(quote.amount_out, 19_752);
+            assert_eq!(quote.minimum_amount_out, 19_554);
+            assert_eq!(quote.price_impact_bps, 99);
+            assert_eq!(quote.swap_instruction().minimum_amount_out, 19_554);
+
+            let exact_out = quote_exact_out(&reserves, SwapDirection::CoinToPc, 19_752, &fees(25, 10000)).unwrap();
+            assert!(exact_out.amount_in <= 10_000);
+            assert_eq!(exact_out.minimum_amount_out, 19_752);
+
+            assert_eq!(
+                quote_exact_out(&reserves, SwapDirection::PcToCoin, 1_000_000, &fees(25, 10000)),
+                Err(QuoteError::InsufficientLiquidity)
+            );
+            assert_eq!(
+                quote_exact_in(&PoolReserves { coin: 0, pc: 1 }, SwapDirection::CoinToPc, 1, &fees(25, 10000), 0),
+                Err(QuoteError::EmptyPool)
+            );
+            assert_eq!(
+                quote_exact_in(&reserves, SwapDirection::CoinToPc, 1, &fees(25, 0), 0),
+                Err(QuoteError::InvalidFees)
+            );
+            assert_eq!(
+                quote_exact_in(&reserves, SwapDirection::CoinToPc, 1, &fees(25, 10000), 10001),
+                Err(QuoteError::InvalidSlippage)
+            );
+        }
+
+        fn direction() -> impl Strategy<Value = SwapDirection> {
+            prop_oneof![Just(SwapDirection::CoinToPc), Just(SwapDirection::PcToCoin)]
+        }
+
+        proptest! {
+            #[test]
+            fn test_quote_never_overstates_output(
+                coin in 1u64..1_000_000_000_000_000,
+                pc in 1u64..1_000_000_000_000_000,
+                amount_in in 0u64..1_000_000_000_000_000,
+                swap_fee_denominator in 1u64..1_000_000,
+                fee_ratio in 0u64..=100,
+                slippage_bps in 0u16..=10000,
+                direction in direction(),
+            ) {
+                let fees = fees(swap_fee_denominator * fee_ratio / 100, swap_fee_denominator);
+                let reserves = PoolReserves { coin, pc };
+                let quote = quote_exact_in(&reserves, direction, amount_in, &fees, slippage_bps).unwrap();
+                let (reserve_in, reserve_out) = match direction {
+                    SwapDirection::CoinToPc => (coin as u128, pc as u128),
+                    SwapDirection::PcToCoin => (pc as u128, coin as u128),
+                };
+                // amount_out <= reserve_out * in' / (reserve_in + in') with in' = amount_in * (1 - fee) unrounded
+                let denominator = fees.swap_fee_denominator as u128;
+                let in_after_fee = amount_in as u128 * (denominator - fees.swap_fee_numerator as u128);
+                prop_assert!(
+                    quote.amount_out as u128 * (reserve_in * denominator + in_after_fee) <= reserve_out * in_after_fee
+                );
+                prop_assert!(quote.amount_out < reserve_out as u64);
+                prop_assert!(quote.minimum_amount_out <= quote.amount_out);
+            }
+
+            #[test]
+            fn test_quote_exact_out_is_smallest_input(
+                coin in 1u64..1_000_000_000_000,
+                pc in 1u64..1_000_000_000_000,
+                out_ratio in 0u64..1000,
+                swap_fee_numerator in 0u64..1000,
+                direction in direction(),
+            ) {
+                let fees = fees(swap_fee_numerator, 10000);
+                let reserves = PoolReserves { coin, pc };
+                let reserve_out = match direction {
+                    SwapDirection::CoinToPc => pc,
+                    SwapDirection::PcToCoin => coin,
+                };
+                let amount_out = reserve_out / 1000 * out_ratio;
+                let quote = quote_exact_out(&reserves, direction, amount_out, &fees).unwrap();
+                prop_assert!(quote.amount_out >= amount_out);
+                let exact_in = quote_exact_in(&reserves, direction, quote.amount_in, &fees, 0).unwrap();
+                prop_assert_eq!(exact_in.amount_out, quote.amount_out);
+                if quote.amount_in > 0 {
+                    let lower = quote_exact_in(&reserves, direction, quote.amount_in - 1, &fees, 0).unwrap();
+                    prop_assert!(lower.amount_out < amount_out);
+                }
+            }
+        }
+    }
+}
+
 /// Unpacks a reference from a bytes buffer.
 /// TODO actually pack / unpack instead of relying on normal memory layout.
 pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
@@ -1041,4 +3207,711 @@ pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
     #[allow(clippy::cast_ptr_alignment)]
     let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
     Ok(val)
-}
\ No newline at end of file
+}
+/// Borrowed views of the instructions indexers decode the most. `new` checks the data once,
+/// failing as [AmmInstruction::unpack](../enum.AmmInstruction.html#method.unpack), then each
+/// field is read from the data when asked, without copying it.
+pub mod view {
+    use super::{AmmInstruction, SwapInstruction};
+    use crate::error::AmmError;
+    use program_instruction::InstructionLayout;
+    use solana_program::program_error::ProgramError;
+    use std::{convert::TryInto, ops::Range};
+
+    const SWAP_TAG: u8 = 9;
+
+    const SWAP: &InstructionLayout = InstructionLayout::find(AmmInstruction::LAYOUTS, SWAP_TAG);
+
+    // Byte ranges of the fields, tag included, a field missing from the layout fails the build
+    const AMOUNT_IN: Range<usize> = SWAP.field_range("amount_in");
+    const MINIMUM_AMOUNT_OUT: Range<usize> = SWAP.field_range("minimum_amount_out");
+    const SWAP_LEN: usize = SWAP.size();
+
+    fn read_u64(data: &[u8], range: Range<usize>) -> u64 {
+        u64::from_le_bytes(data[range].try_into().unwrap())
+    }
+
+    /// [AmmInstruction::Swap](../enum.AmmInstruction.html#variant.Swap) data, the bytes after
+    /// its last field are ignored as by `unpack`
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct SwapView<'a> {
+        data: &'a [u8],
+    }
+
+    impl<'a> SwapView<'a> {
+        pub fn new(data: &'a [u8]) -> Result<Self, ProgramError> {
+            if data.first() != Some(&SWAP_TAG) || data.len() < SWAP_LEN {
+                return Err(AmmError::InvalidInstruction.into());
+            }
+            Ok(Self { data })
+        }
+
+        pub fn amount_in(&self) -> u64 {
+            read_u64(self.data, AMOUNT_IN)
+        }
+
+        pub fn minimum_amount_out(&self) -> u64 {
+            read_u64(self.data, MINIMUM_AMOUNT_OUT)
+        }
+
+        pub fn instruction(&self) -> AmmInstruction {
+            AmmInstruction::Swap(SwapInstruction {
+                amount_in: self.amount_in(),
+                minimum_amount_out: self.minimum_amount_out(),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::{SwapView, AMOUNT_IN, MINIMUM_AMOUNT_OUT, SWAP_LEN, SWAP_TAG};
+        use crate::instruction::{AmmInstruction, SwapInstruction, WithdrawInstruction};
+
+        #[test]
+        fn test_swap_view() {
+            let layout = AmmInstruction::layout(SWAP_TAG).unwrap();
+            assert_eq!(layout.ranges(), vec![("amount_in", AMOUNT_IN), ("minimum_amount_out", MINIMUM_AMOUNT_OUT)]);
+            assert_eq!(layout.size(), SWAP_LEN);
+
+            let swap = AmmInstruction::Swap(SwapInstruction { amount_in: 10_000, minimum_amount_out: u64::MAX });
+            let mut data = swap.pack().unwrap();
+            let view = SwapView::new(&data).unwrap();
+            assert_eq!(view.amount_in(), 10_000);
+            assert_eq!(view.minimum_amount_out(), u64::MAX);
+            assert_eq!(view.instruction(), swap);
+            for len in 0..data.len() {
+                assert_eq!(SwapView::new(&data[..len]).unwrap_err(), AmmInstruction::unpack(&data[..len]).unwrap_err());
+            }
+            data.push(0);
+            assert_eq!(SwapView::new(&data).unwrap().instruction(), AmmInstruction::unpack(&data).unwrap());
+            let withdraw = AmmInstruction::Withdraw(WithdrawInstruction { amount: 1 }).pack().unwrap();
+            assert!(SwapView::new(&withdraw).is_err());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+    use program_instruction::{test_utils::check_vectors, ProgramInstruction};
+
+    use crate::{
+        error::AmmError,
+        state::{AmmParams, Fees},
+    };
+
+    use super::{
+        set_params, swap, transaction_size, withdraw, withdraw_transfer, withdraw_transfer_batches, AmmInstruction,
+        BuildError, DepositInstruction, InitializeInstruction, MonitorStepInstruction, SetParams, SetParamsAccounts,
+        SetParamsInstruction, StrictUnpackError, SwapAccounts, SwapBuilder, SwapInstruction, ValueParam,
+        WithdrawBuilder, WithdrawInstruction, WithdrawTransferAccounts, WithdrawTransferInstruction,
+        PACKET_DATA_SIZE,
+    };
+
+    fn swap_accounts() -> SwapAccounts {
+        SwapAccounts {
+            spl_token_program: spl_token::id(),
+            amm_id: Pubkey::new_unique(),
+            amm_authority: Pubkey::new_unique(),
+            amm_open_orders: Pubkey::new_unique(),
+            amm_target_orders: Pubkey::new_unique(),
+            pool_coin_token_account: Pubkey::new_unique(),
+            pool_pc_token_account: Pubkey::new_unique(),
+            serum_program_id: Pubkey::new_unique(),
+            serum_market: Pubkey::new_unique(),
+            serum_bids: Pubkey::new_unique(),
+            serum_asks: Pubkey::new_unique(),
+            serum_event_queue: Pubkey::new_unique(),
+            serum_coin_vault_account: Pubkey::new_unique(),
+            serum_pc_vault_account: Pubkey::new_unique(),
+            serum_vault_signer: Pubkey::new_unique(),
+            user_source_token_account: Pubkey::new_unique(),
+            user_destination_token_account: Pubkey::new_unique(),
+            user_source_owner: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn test_accounts_match_builders() {
+        let program_id = Pubkey::new_unique();
+        let a = swap_accounts();
+        let instruction = swap(
+            &program_id,
+            &a.amm_id,
+            &a.amm_authority,
+            &a.amm_open_orders,
+            &a.amm_target_orders,
+            &a.pool_coin_token_account,
+            &a.pool_pc_token_account,
+            &a.serum_program_id,
+            &a.serum_market,
+            &a.serum_bids,
+            &a.serum_asks,
+            &a.serum_event_queue,
+            &a.serum_coin_vault_account,
+            &a.serum_pc_vault_account,
+            &a.serum_vault_signer,
+            &a.user_source_token_account,
+            &a.user_destination_token_account,
+            &a.user_source_owner,
+            100,
+            90,
+        )
+        .unwrap();
+        assert_eq!(instruction.accounts, a.to_account_metas());
+        assert_eq!(instruction.accounts.len(), SwapAccounts::<Pubkey>::LEN);
+
+        let keys: Vec<Pubkey> = (0..WithdrawTransferAccounts::<Pubkey>::LEN).map(|_| Pubkey::new_unique()).collect();
+        let withdraw_dest_accounts = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let a = WithdrawTransferAccounts {
+            spl_token_program: spl_token::id(),
+            amm_id: keys[1],
+            amm_authority: keys[2],
+            amm_open_orders: keys[3],
+            lp_mint_address: keys[4],
+            pool_coin_token_account: keys[5],
+            pool_pc_token_account: keys[6],
+            pool_withdraw_queue: keys[7],
+            pool_temp_lp_token_account: keys[8],
+            serum_program_id: keys[9],
+            serum_market: keys[10],
+            serum_coin_vault_account: keys[11],
+            serum_pc_vault_account: keys[12],
+            serum_vault_signer: keys[13],
+            withdraw_dest_accounts: withdraw_dest_accounts.clone(),
+        };
+        let instruction = withdraw_transfer(
+            &program_id,
+            &keys[1],
+            &keys[2],
+            &keys[3],
+            &keys[4],
+            &keys[5],
+            &keys[6],
+            &keys[7],
+            &keys[8],
+            &keys[9],
+            &keys[10],
+            &keys[11],
+            &keys[12],
+            &keys[13],
+            &withdraw_dest_accounts,
+            // one dest_coin and dest_pc pair, so at most one entry
+            1,
+        )
+        .unwrap();
+        assert_eq!(instruction.accounts, a.to_account_metas());
+    }
+
+    #[test]
+    fn test_builders() {
+        let program_id = Pubkey::new_unique();
+        let a = swap_accounts();
+        let builder = SwapBuilder::new(&program_id)
+            .amm_id(a.amm_id)
+            .amm_authority(a.amm_authority)
+            .amm_open_orders(a.amm_open_orders)
+            .amm_target_orders(a.amm_target_orders)
+            .pool_coin_token_account(a.pool_coin_token_account)
+            .pool_pc_token_account(a.pool_pc_token_account)
+            .serum_program_id(a.serum_program_id)
+            .serum_market(a.serum_market)
+            .serum_bids(a.serum_bids)
+            .serum_asks(a.serum_asks)
+            .serum_event_queue(a.serum_event_queue)
+            .serum_coin_vault_account(a.serum_coin_vault_account)
+            .serum_pc_vault_account(a.serum_pc_vault_account)
+            .serum_vault_signer(a.serum_vault_signer)
+            .user_source_token_account(a.user_source_token_account)
+            .user_destination_token_account(a.user_destination_token_account);
+        assert_eq!(
+            builder.build(100, 90),
+            Err(BuildError::MissingAccount("user_source_owner"))
+        );
+        let builder = builder.user_source_owner(a.user_source_owner);
+        assert_eq!(builder.accounts(), Ok(a.clone()));
+        let instruction = builder.build(100, 90).unwrap();
+        assert_eq!(
+            instruction,
+            swap(
+                &program_id,
+                &a.amm_id,
+                &a.amm_authority,
+                &a.amm_open_orders,
+                &a.amm_target_orders,
+                &a.pool_coin_token_account,
+                &a.pool_pc_token_account,
+                &a.serum_program_id,
+                &a.serum_market,
+                &a.serum_bids,
+                &a.serum_asks,
+                &a.serum_event_queue,
+                &a.serum_coin_vault_account,
+                &a.serum_pc_vault_account,
+                &a.serum_vault_signer,
+                &a.user_source_token_account,
+                &a.user_destination_token_account,
+                &a.user_source_owner,
+                100,
+                90,
+            )
+            .unwrap()
+        );
+        let token_program = Pubkey::new_unique();
+        let accounts = builder.spl_token_program(token_program).accounts().unwrap();
+        assert_eq!(accounts.spl_token_program, token_program);
+
+        let keys: Vec<Pubkey> = (0..19).map(|_| Pubkey::new_unique()).collect();
+        let builder = WithdrawBuilder::new(&program_id)
+            .amm_id(keys[1])
+            .amm_authority(keys[2])
+            .amm_open_orders(keys[3])
+            .amm_target_orders(keys[4])
+            .lp_mint_address(keys[5])
+            .pool_coin_token_account(keys[6])
+            .pool_pc_token_account(keys[7])
+            .pool_withdraw_queue(keys[8])
+            .pool_temp_lp_token_account(keys[9])
+            .serum_program_id(keys[10])
+            .serum_market(keys[11])
+            .serum_coin_vault_account(keys[12])
+            .serum_pc_vault_account(keys[13])
+            .serum_vault_signer(keys[14])
+            .user_lp_token_account(keys[15])
+            .user_pc_token_account(keys[17])
+            .user_owner(keys[18]);
+        let err = builder.build(5).unwrap_err();
+        assert_eq!(err, BuildError::MissingAccount("user_coin_token_account"));
+        assert_eq!(ProgramError::from(err), ProgramError::NotEnoughAccountKeys);
+        let instruction = builder.user_coin_token_account(keys[16]).build(5).unwrap();
+        assert_eq!(
+            instruction,
+            withdraw(
+                &program_id,
+                &keys[1],
+                &keys[2],
+                &keys[3],
+                &keys[4],
+                &keys[5],
+                &keys[6],
+                &keys[7],
+                &keys[8],
+                &keys[9],
+                &keys[10],
+                &keys[11],
+                &keys[12],
+                &keys[13],
+                &keys[14],
+                &keys[15],
+                &keys[16],
+                &keys[17],
+                &keys[18],
+                5,
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_transfer_batches() {
+        let program_id = Pubkey::new_unique();
+        let keys: Vec<Pubkey> = (0..WithdrawTransferAccounts::<Pubkey>::LEN).map(|_| Pubkey::new_unique()).collect();
+        let accounts = |withdraw_dest_accounts: Vec<Pubkey>| WithdrawTransferAccounts {
+            spl_token_program: spl_token::id(),
+            amm_id: keys[1],
+            amm_authority: keys[2],
+            amm_open_orders: keys[3],
+            lp_mint_address: keys[4],
+            pool_coin_token_account: keys[5],
+            pool_pc_token_account: keys[6],
+            pool_withdraw_queue: keys[7],
+            pool_temp_lp_token_account: keys[8],
+            serum_program_id: keys[9],
+            serum_market: keys[10],
+            serum_coin_vault_account: keys[11],
+            serum_pc_vault_account: keys[12],
+            serum_vault_signer: keys[13],
+            withdraw_dest_accounts,
+        };
+        assert_eq!(withdraw_transfer_batches(&program_id, &accounts(vec![])), Ok(vec![]));
+        let err = withdraw_transfer_batches(&program_id, &accounts(vec![Pubkey::new_unique()])).unwrap_err();
+        assert_eq!(err, BuildError::UnpairedWithdrawDestinations(1));
+        assert_eq!(ProgramError::from(err), AmmError::InvalidInput.into());
+
+        let dest: Vec<Pubkey> = (0..50).map(|_| Pubkey::new_unique()).collect();
+        let batches = withdraw_transfer_batches(&program_id, &accounts(dest.clone())).unwrap();
+        let limits: Vec<u16> = batches
+            .iter()
+            .map(|instruction| match AmmInstruction::unpack(&instruction.data).unwrap() {
+                AmmInstruction::WithdrawTransfer(WithdrawTransferInstruction { limit }) => limit,
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        // 634 bytes plus 66 per entry of new keys
+        assert_eq!(limits, vec![9, 9, 7]);
+        let mut paid_out = vec![];
+        for (instruction, limit) in batches.iter().zip(limits) {
+            assert_eq!(instruction.program_id, program_id);
+            let (pool, withdraw_dest_accounts) = instruction.accounts.split_at(WithdrawTransferAccounts::<Pubkey>::LEN);
+            assert_eq!(pool, &accounts(vec![]).to_account_metas()[..]);
+            assert_eq!(withdraw_dest_accounts.len(), 2 * limit as usize);
+            assert!(withdraw_dest_accounts.iter().all(|meta| meta.is_writable));
+            paid_out.extend(withdraw_dest_accounts.iter().map(|meta| meta.pubkey));
+            let num_keys = instruction.accounts.len() + 2;
+            assert!(transaction_size(num_keys, instruction.accounts.len(), instruction.data.len()) <= PACKET_DATA_SIZE);
+        }
+        assert_eq!(paid_out, dest);
+        let single = withdraw_transfer(
+            &program_id,
+            &keys[1],
+            &keys[2],
+            &keys[3],
+            &keys[4],
+            &keys[5],
+            &keys[6],
+            &keys[7],
+            &keys[8],
+            &keys[9],
+            &keys[10],
+            &keys[11],
+            &keys[12],
+            &keys[13],
+            &dest[36..],
+            7,
+        )
+        .unwrap();
+        assert_eq!(batches[2], single);
+
+        // The keys of a destination that comes back are only counted once
+        let dest = [dest[0], dest[1]].repeat(20);
+        let batches = withdraw_transfer_batches(&program_id, &accounts(dest)).unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].data, vec![5, 20, 0]);
+    }
+
+    #[test]
+    fn test_try_from_account_infos() {
+        let metas = swap_accounts().to_account_metas();
+        let owner = Pubkey::new_unique();
+        let mut lamports = vec![0u64; metas.len()];
+        let mut data = vec![[0u8; 0]; metas.len()];
+        let mut account_infos: Vec<AccountInfo> = metas
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((meta, lamports), data)| {
+                AccountInfo::new(&meta.pubkey, meta.is_signer, meta.is_writable, lamports, data, &owner, false, 0)
+            })
+            .collect();
+
+        let parsed = SwapAccounts::try_from_account_infos(&account_infos).unwrap();
+        assert_eq!(*parsed.user_source_owner.key, metas[17].pubkey);
+
+        assert_eq!(
+            SwapAccounts::try_from_account_infos(&account_infos[..17]).unwrap_err(),
+            ProgramError::NotEnoughAccountKeys
+        );
+        account_infos[1].is_writable = false;
+        assert_eq!(
+            SwapAccounts::try_from_account_infos(&account_infos).unwrap_err(),
+            ProgramError::InvalidArgument
+        );
+        account_infos[1].is_writable = true;
+        account_infos[17].is_signer = false;
+        assert_eq!(
+            SwapAccounts::try_from_account_infos(&account_infos).unwrap_err(),
+            ProgramError::MissingRequiredSignature
+        );
+    }
+
+    #[test]
+    fn test_set_params_accounts() {
+        let keys = (0..6).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        let update_open_orders = SetParams::Value(ValueParam::new(AmmParams::UpdateOpenOrder).unwrap(), 1);
+        let cases = vec![
+            (SetParams::Value(ValueParam::new(AmmParams::Status).unwrap(), 1), None, None),
+            (SetParams::AmmOwner(new_owner), None, None),
+            (update_open_orders, Some(&keys[4]), Some(&keys[5])),
+        ];
+        for (params, open_orders, target_orders) in cases {
+            let instruction = set_params(&keys[0], &keys[1], &keys[2], &keys[3], params, open_orders, target_orders).unwrap();
+            let metas = instruction.accounts;
+            assert_eq!(metas.len(), SetParamsAccounts::<Pubkey>::len_with(&params));
+            assert!(AmmInstruction::SetParams(params.into()).expected_account_count().contains(metas.len()));
+            let mut lamports = vec![0u64; metas.len()];
+            let mut data = vec![vec![]; metas.len()];
+            let account_infos: Vec<AccountInfo> = metas
+                .iter()
+                .zip(lamports.iter_mut())
+                .zip(data.iter_mut())
+                .map(|((meta, lamports), data)| {
+                    AccountInfo::new(&meta.pubkey, meta.is_signer, meta.is_writable, lamports, data, &owner, false, 0)
+                })
+                .collect();
+
+            let parsed = SetParamsAccounts::try_from_account_infos(&account_infos, &params).unwrap();
+            assert_eq!(*parsed.amm_owner_account.key, keys[3]);
+            assert_eq!(parsed.new_owner.map(|info| *info.key), SetParamsInstruction::from(params).new_pubkey);
+            assert_eq!(parsed.amm_target_orders.map(|info| *info.key), target_orders.copied());
+            assert_eq!(
+                SetParamsAccounts::try_from_account_infos(&account_infos[..metas.len() - 1], &params).unwrap_err(),
+                ProgramError::NotEnoughAccountKeys
+            );
+        }
+
+        // 6 accounts, a new owner and the open orders pair, is no valid combination
+        let metas = SetParamsAccounts {
+            amm_id: keys[0],
+            amm_authority: keys[1],
+            amm_owner_account: keys[2],
+            new_owner: Some(keys[3]),
+            amm_open_orders: Some(keys[4]),
+            amm_target_orders: Some(keys[5]),
+        }
+        .to_account_metas();
+        let mut lamports = vec![0u64; metas.len()];
+        let mut data = vec![vec![]; metas.len()];
+        let account_infos: Vec<AccountInfo> = metas
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((meta, lamports), data)| {
+                AccountInfo::new(&meta.pubkey, meta.is_signer, meta.is_writable, lamports, data, &owner, false, 0)
+            })
+            .collect();
+        for params in [SetParams::AmmOwner(new_owner), update_open_orders] {
+            assert_eq!(
+                SetParamsAccounts::try_from_account_infos(&account_infos, &params).unwrap_err(),
+                ProgramError::InvalidArgument
+            );
+        }
+    }
+
+    #[test]
+    fn test_program_instruction() {
+        let instructions = vec![
+            AmmInstruction::Initialize(InitializeInstruction { nonce: 254 }),
+            AmmInstruction::Initialize2,
+            AmmInstruction::Deposit(DepositInstruction { max_coin_amount: 1, max_pc_amount: 2, base_side: 0 }),
+            AmmInstruction::WithdrawPnl,
+            AmmInstruction::Swap(SwapInstruction { amount_in: 100, minimum_amount_out: 90 }),
+            AmmInstruction::PreInitialize(InitializeInstruction { nonce: 1 }),
+        ];
+        for instruction in instructions {
+            let data = ProgramInstruction::pack(&instruction).unwrap();
+            assert_eq!(data[0], instruction.discriminant());
+            let unpacked = <AmmInstruction as ProgramInstruction>::unpack(&data).unwrap();
+            assert_eq!(unpacked.variant_name(), instruction.variant_name());
+        }
+        let swap = AmmInstruction::Swap(SwapInstruction { amount_in: 1, minimum_amount_out: 1 });
+        assert!(swap.expected_account_count().contains(18));
+        assert!(!swap.expected_account_count().contains(17));
+    }
+
+    #[test]
+    fn test_set_params() {
+        let fees = Fees {
+            min_separate_numerator: 5,
+            min_separate_denominator: 10000,
+            trade_fee_numerator: 25,
+            trade_fee_denominator: 10000,
+            pnl_numerator: 12,
+            pnl_denominator: 100,
+            swap_fee_numerator: 25,
+            swap_fee_denominator: 10000,
+        };
+        let all_params = vec![
+            SetParams::AmmOwner(Pubkey::new_unique()),
+            SetParams::PnlOwner(Pubkey::new_unique()),
+            SetParams::Fees(fees),
+            SetParams::Value(ValueParam::new(AmmParams::OrderNum).unwrap(), 7),
+        ];
+        for params in all_params {
+            let data = AmmInstruction::SetParams(params.into()).pack().unwrap();
+            match AmmInstruction::unpack(&data).unwrap() {
+                AmmInstruction::SetParams(instruction) => assert_eq!(SetParams::try_from(instruction), Ok(params)),
+                _ => panic!("not SetParams"),
+            }
+        }
+        assert_eq!(ValueParam::new(AmmParams::Fees), None);
+        let missing_pubkey = SetParamsInstruction {
+            param: AmmParams::AmmOwner as u8,
+            value: Some(1),
+            new_pubkey: None,
+            fees: None,
+        };
+        assert_eq!(SetParams::try_from(missing_pubkey), Err(AmmError::InvalidInput));
+        // Params past the last AmmParams are rejected instead of panicking
+        let unknown_param = SetParamsInstruction {
+            param: AmmParams::UpdateOpenOrder as u8 + 1,
+            value: Some(1),
+            new_pubkey: None,
+            fees: None,
+        };
+        assert_eq!(SetParams::try_from(unknown_param), Err(AmmError::InvalidInput));
+        assert!(AmmInstruction::SetParams(unknown_param).pack().is_err());
+        assert!(AmmInstruction::unpack(&[6, 13, 1, 0, 0, 0, 0, 0, 0, 0]).is_err());
+        assert!(AmmInstruction::unpack(&[6, 255]).is_err());
+
+        let keys = (0..6).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let new_owner = Pubkey::new_unique();
+        let owner_params = SetParams::AmmOwner(new_owner);
+        let instruction = set_params(&keys[0], &keys[1], &keys[2], &keys[3], owner_params, None, None).unwrap();
+        assert_eq!(instruction.accounts.len(), 4);
+        assert_eq!(instruction.accounts[3].pubkey, new_owner);
+        let update_open_orders = SetParams::Value(ValueParam::new(AmmParams::UpdateOpenOrder).unwrap(), 1);
+        assert_eq!(
+            set_params(&keys[0], &keys[1], &keys[2], &keys[3], update_open_orders, Some(&keys[4]), None),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        let instruction = set_params(&keys[0], &keys[1], &keys[2], &keys[3], update_open_orders, Some(&keys[4]), Some(&keys[5])).unwrap();
+        assert_eq!(instruction.accounts.len(), 5);
+        assert_eq!(instruction.accounts[3].pubkey, keys[4]);
+        assert!(instruction.accounts[4].is_writable);
+    }
+
+    #[test]
+    fn test_abi() {
+        let abi = AmmInstruction::abi();
+        let instructions = vec![
+            AmmInstruction::Initialize(InitializeInstruction { nonce: 254 }),
+            AmmInstruction::Initialize2,
+            AmmInstruction::Deposit(DepositInstruction { max_coin_amount: 1, max_pc_amount: 2, base_side: 0 }),
+            AmmInstruction::WithdrawPnl,
+            AmmInstruction::Swap(SwapInstruction { amount_in: 100, minimum_amount_out: 90 }),
+            AmmInstruction::PreInitialize(InitializeInstruction { nonce: 1 }),
+        ];
+        for instruction in instructions {
+            let entry = abi.instructions.iter().find(|entry| entry.code == instruction.discriminant()).unwrap();
+            assert_eq!(entry.name, instruction.variant_name());
+            let required = entry.accounts.iter().filter(|account| !account.is_optional).count();
+            assert!(instruction.expected_account_count().contains(required));
+            assert!(instruction.expected_account_count().contains(entry.accounts.len()));
+        }
+        let swap = abi.instructions.iter().find(|entry| entry.name == "Swap").unwrap();
+        let metas = swap_accounts().to_account_metas();
+        assert_eq!(swap.accounts.len(), metas.len());
+        for (account, meta) in swap.accounts.iter().zip(metas.iter()) {
+            assert_eq!(account.is_signer, meta.is_signer);
+            assert_eq!(account.is_readonly, !meta.is_writable);
+        }
+        let set_params = abi.instructions.iter().find(|entry| entry.name == "SetParams").unwrap();
+        let fees = set_params.inputs.iter().find(|input| input.name == "fees").unwrap();
+        assert!(fees.when.as_ref().unwrap().holds(AmmParams::Fees as u64));
+        assert!(!fees.when.as_ref().unwrap().holds(AmmParams::AmmOwner as u64));
+    }
+
+    #[test]
+    fn test_strict_unpack() {
+        let withdraw = AmmInstruction::Withdraw(WithdrawInstruction { amount: 7 });
+        let mut data = withdraw.pack().unwrap();
+        assert_eq!(AmmInstruction::unpack_strict(&data), Ok(withdraw.clone()));
+        data.extend_from_slice(&[0xaa; 40]);
+        assert_eq!(AmmInstruction::unpack(&data).unwrap(), withdraw);
+        let err = AmmInstruction::unpack_strict(&data).unwrap_err();
+        assert_eq!(
+            err,
+            StrictUnpackError::TrailingBytes {
+                tag: 4,
+                expected_len: 9,
+                trailing_len: 40,
+            }
+        );
+        assert_eq!(ProgramError::from(err), AmmError::InvalidInstruction.into());
+
+        let fees = Fees { swap_fee_numerator: 25, swap_fee_denominator: 10000, ..Fees::default() };
+        let all_params = vec![
+            SetParams::AmmOwner(Pubkey::new_unique()),
+            SetParams::Fees(fees),
+            SetParams::Value(ValueParam::new(AmmParams::Depth).unwrap(), 3),
+        ];
+        for params in all_params {
+            let instruction = AmmInstruction::SetParams(params.into());
+            let mut data = instruction.pack().unwrap();
+            assert_eq!(AmmInstruction::unpack_strict(&data), Ok(instruction));
+            let expected_len = data.len();
+            data.push(0);
+            assert_eq!(
+                AmmInstruction::unpack_strict(&data),
+                Err(StrictUnpackError::TrailingBytes {
+                    tag: 6,
+                    expected_len,
+                    trailing_len: 1,
+                })
+            );
+        }
+
+        assert_eq!(
+            AmmInstruction::unpack_strict(&[9, 1]),
+            Err(StrictUnpackError::Invalid(AmmError::InvalidInstruction.into()))
+        );
+        assert!(AmmInstruction::unpack_strict(&[]).is_err());
+    }
+
+    #[test]
+    fn test_layouts() {
+        let instructions = vec![
+            AmmInstruction::Initialize(InitializeInstruction { nonce: 1 }),
+            AmmInstruction::Initialize2,
+            AmmInstruction::MonitorStep(MonitorStepInstruction {
+                plan_order_limit: 1,
+                place_order_limit: 2,
+                cancel_order_limit: 3,
+            }),
+            AmmInstruction::WithdrawPnl,
+            AmmInstruction::Swap(SwapInstruction { amount_in: 100, minimum_amount_out: 90 }),
+        ];
+        for instruction in instructions {
+            let packed = instruction.pack().unwrap();
+            let layout = AmmInstruction::layout(packed[0]).unwrap();
+            assert_eq!(layout.name, instruction.variant_name());
+            assert_eq!(packed.len(), layout.size());
+            assert_eq!(AmmInstruction::unpack(&packed).unwrap(), instruction);
+        }
+        let swap = AmmInstruction::layout(9).unwrap();
+        assert_eq!(swap.range("minimum_amount_out"), Some(9..17));
+        assert_eq!(swap.memcmp::<u64>("amount_in", &100), Some((1, 100u64.to_le_bytes().to_vec())));
+        let set_params = AmmInstruction::layout(6).unwrap();
+        assert!(set_params.tail);
+        assert_eq!(set_params.size(), 2);
+        assert!(AmmInstruction::unpack(&[9, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn test_serde() {
+        let swap = AmmInstruction::Swap(SwapInstruction { amount_in: 100, minimum_amount_out: 90 });
+        let json = serde_json::to_string(&swap).unwrap();
+        assert_eq!(json, r#"{"instruction":"Swap","args":{"amount_in":100,"minimum_amount_out":90}}"#);
+        assert_eq!(serde_json::from_str::<AmmInstruction>(&json).unwrap(), swap);
+        assert_eq!(serde_json::to_string(&AmmInstruction::WithdrawPnl).unwrap(), r#"{"instruction":"WithdrawPnl"}"#);
+
+        let fees = Fees { swap_fee_numerator: 25, swap_fee_denominator: 10000, ..Fees::default() };
+        let set_params = AmmInstruction::SetParams(SetParams::Fees(fees).into());
+        let json = serde_json::to_value(&set_params).unwrap();
+        assert_eq!(json["args"]["fees"]["swap_fee_denominator"], 10000);
+        assert_eq!(serde_json::from_value::<AmmInstruction>(json).unwrap(), set_params);
+    }
+
+    /// Decodes and encodes every vector of raydium.rs_vectors.json, any wire format change fails
+    #[test]
+    fn test_vectors() {
+        let mut vectors = serde_json::Deserializer::from_str(include_str!("raydium.rs_vectors.json"));
+        let instructions = check_vectors(&mut vectors, |instruction: &AmmInstruction, _| instruction.pack().unwrap());
+        let mut params = std::collections::BTreeSet::new();
+        for instruction in instructions {
+            let data = instruction.pack().unwrap();
+            assert_eq!(AmmInstruction::unpack_strict(&data), Ok(instruction.clone()), "unpack_strict {:?}", instruction);
+            if let AmmInstruction::SetParams(SetParamsInstruction { param, .. }) = instruction {
+                params.insert(param as u64);
+            }
+        }
+        for param in AmmParams::Status as u64..=AmmParams::UpdateOpenOrder as u64 {
+            assert!(params.contains(&param), "no vector for SetParams {}", param);
+        }
+    }
+}
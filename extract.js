@@ -1,4 +1,3 @@
-const ast = require("./examples/inst.rs_parsed.json");
 
 const Instruction = "solana_program::instruction::Instruction";
 const ProgramError = "solana_program::program_error::ProgramError";
@@ -132,12 +131,13 @@ function getStruct(structDef) {
 }
 
 function parseImpl(implStruct) {
-  const implName = implStruct.self_ty.path.segments[0].ident.to_string;
   const allInstructions = [];
   // implStruct.items
   let okMatch = null;
   for (let index = 0; index < implStruct.items.length; index++) {
     const ImplItemMethod = implStruct.items[index];
+    // associated consts and types
+    if (ImplItemMethod._type != "ImplItemMethod") continue;
     const methodName = ImplItemMethod.sig.ident.to_string;
     if (methodName != "unpack") continue;
 
@@ -182,6 +182,59 @@ function parseImpl(implStruct) {
   return allInstructions;
 }
 
+// instruction_layouts! { impl X { fixed { 0 => Init(InitArgs) { .. }, 1 => Create { .. }, } } }
+// declares the unpack arms of X, read them from its tokens
+function parseLayoutsMacro(itemMacro) {
+  const allInstructions = [];
+  function walkTokens(tokens) {
+    for (let index = 0; index < tokens.length; index++) {
+      const token = tokens[index];
+      if (token._type == "Group") {
+        walkTokens(token.stream);
+        continue;
+      }
+      const arrow = [tokens[index + 1], tokens[index + 2]];
+      const variant = tokens[index + 3];
+      if (
+        token._type != "Literal" ||
+        arrow.some((_) => !_ || _._type != "Punct") ||
+        arrow.map((_) => _.as_char).join("") != "=>" ||
+        !variant ||
+        variant._type != "Ident"
+      )
+        continue;
+      const args = tokens[index + 4];
+      const isArgs =
+        args &&
+        args._type == "Group" &&
+        args.delimiter._type == "Delimiter::Parenthesis";
+      allInstructions.push({
+        instructionCode: token.to_string,
+        instructionName: isArgs
+          ? args.stream[0].to_string
+          : `Self::${variant.to_string}`,
+      });
+      index += isArgs ? 4 : 3;
+    }
+  }
+  walkTokens(itemMacro.mac.tokens);
+  return allInstructions;
+}
+
+// #[cfg(not(...))] variants only stand in for others and have no code of their own
+function isCfgNot(attrs) {
+  for (let index = 0; index < (attrs ? attrs.length : 0); index++) {
+    const attr = attrs[index];
+    if (attr._type != "Attribute") continue;
+    const path = attr.path.segments[0];
+    const group = attr.tokens && attr.tokens[0];
+    if (path.ident.to_string != "cfg" || !group || !group.stream) continue;
+    const first = group.stream[0];
+    if (first && first._type == "Ident" && first.to_string == "not") return true;
+  }
+  return false;
+}
+
 function parseEnum(itemEnum) {
   const enumInstructions = [];
   const enumName = itemEnum.ident.to_string;
@@ -190,10 +243,12 @@ function parseEnum(itemEnum) {
   for (let index = 0; index < itemEnum.variants.length; index++) {
     const variant = itemEnum.variants[index];
     if (variant._type != "Variant") continue;
+    if (isCfgNot(variant.attrs)) continue;
     const unamed = variant.fields.unnamed;
     let instructionName = null;
     const enumName = variant.ident.to_string;
-    if (unamed) {
+    // references and tuples like MissingAccount(&'static str) have no path
+    if (unamed && unamed[0].ty.path) {
       instructionName = unamed[0].ty.path.segments[0].ident.to_string;
       //   console.log(
       //     "instructName",
@@ -505,8 +560,9 @@ function getFn(fn) {
 
   // console.log('exprStructs', exprStructs)
 
-  const accountsVar = localVars.find((_) => _.name == "accounts");
-  const dataVar = localVars.find((_) => _.name == "data");
+  // arguments of the same name, like accounts: &WithdrawTransferAccounts, have no varItems
+  const accountsVar = localVars.find((_) => _.name == "accounts" && !_.isArg);
+  const dataVar = localVars.find((_) => _.name == "data" && !_.isArg);
   const InstructionExpr = exprStructs.find(
     (_) => _.name.indexOf("Instruction") > -1
   );
@@ -514,7 +570,7 @@ function getFn(fn) {
   const isInstructionFn = outputTypePath.indexOf("Instruction") > -1;
 
   let refInstruction = dataVar && dataVar.varItems && dataVar.varItems[0];
-  if (refInstruction && refInstruction.indexOf('Instruction' == -1)) {
+  if (refInstruction && refInstruction.indexOf('Instruction') == -1) {
     const parentVar = localVars.find(_ => _.name == refInstruction);
     refInstruction = parentVar && parentVar.varItems && parentVar.varItems[0];
   }
@@ -624,6 +680,14 @@ function normalizeInput(inputs) {
   });
 }
 
+// `try_x` builders are wrapped by an `x` that unwraps them, keep the name callers know
+function opNameOf(fn, allFucs) {
+  const wrapper = fn.name.replace(/^try_/, "");
+  return wrapper != fn.name && allFucs.some((_) => _.name == wrapper)
+    ? wrapper
+    : fn.name;
+}
+
 function parseAST(ast) {
   const useTypes = new Set();
 
@@ -682,6 +746,15 @@ function parseAST(ast) {
       //    structs.set(struct.name, struct);
     }
 
+    if (
+      item._type == "ItemMacro" &&
+      getPathName(item.mac.path).join("") == "instruction_layouts"
+    ) {
+      parseLayoutsMacro(item).forEach((_) => {
+        implInsts.set(_.instructionCode, _.instructionName);
+      });
+    }
+
     if (item._type == "ItemEnum") {
       const enumIn = parseEnum(item);
       enumIn.enumInstructions.forEach((_) => {
@@ -739,7 +812,7 @@ function parseAST(ast) {
     );
     if (instructionRefFunc) {
       instruction.accounts = instructionRefFunc.accounts;
-      instruction.opName = instructionRefFunc.name;
+      instruction.opName = opNameOf(instructionRefFunc, allFucs);
     }
 
     if (argsStructName) {
@@ -773,7 +846,6 @@ function parseAST(ast) {
   };
 }
 
-// parseAST(ast);
 module.exports = {
   parseAST,
 };
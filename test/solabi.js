@@ -0,0 +1,52 @@
+// Checks that solabi names the builders of the bundled programs like their
+// checked-in *_ABI.json files do, and finds as many of them as it used to.
+// Run with `node test/solabi.js`.
+const assert = require("assert");
+const fs = require("fs");
+const path = require("path");
+const syn = require("../syn");
+const { parseAST } = require("../extract");
+
+const examples = path.resolve(__dirname, "../examples");
+// Number of instructions solabi finds the builder of, by file
+const files = {
+  "raydium.rs": 10,
+  "bonfida-bot.rs": 8,
+  "metaplex-token-metadata.rs": 9,
+};
+
+async function main() {
+  const wasm = WebAssembly.compile(
+    fs.readFileSync(path.resolve(__dirname, "../syn/astexplorer_syn_bg.wasm"))
+  );
+  await syn.init(wasm);
+  const log = console.log;
+  for (const [file, builders] of Object.entries(files)) {
+    const ast = await syn.parseFile(
+      fs.readFileSync(path.join(examples, file), "utf-8")
+    );
+    console.log = () => {};
+    const { instructions } = parseAST(ast);
+    console.log = log;
+    const expected = require(path.join(examples, `${file}_ABI.json`));
+    let checked = 0;
+    instructions.forEach((instruction) => {
+      const abi = expected.instructions.find((_) => _.code == instruction.code);
+      assert.ok(abi, `${file}: no instruction ${instruction.code} in the ABI`);
+      if (!instruction.opName) return;
+      assert.strictEqual(
+        instruction.opName,
+        abi.opName,
+        `${file}: opName of ${instruction.name}`
+      );
+      checked++;
+    });
+    assert.strictEqual(checked, builders, `${file}: builders found`);
+    log(`${file}: ${checked} opNames match`);
+  }
+}
+
+main().catch((e) => {
+  console.error(e.message);
+  process.exit(1);
+});